@@ -0,0 +1,149 @@
+use std::{
+    env,
+    fmt::Debug,
+    io::{self, BufRead, Read},
+    process,
+};
+
+use aoc2023::{
+    checked, day1, day15, day2, day4, day7, day9, registry,
+    stream::{self, Accumulator, Records},
+};
+
+const USAGE: &str = "usage: tools stream <day> <part> [-v] | tools explain <day> <part> | \
+     tools solve <day> <part> [params...]   (puzzle input on stdin)";
+
+fn run<R, A>(records: Records<R, A>, verbose: bool) -> io::Result<()>
+where
+    R: BufRead,
+    A: Accumulator,
+    A::Record: Debug,
+    A::Total: Debug,
+{
+    if verbose {
+        for record in records {
+            let (result, total) = record?;
+            println!("{:?}\t{:?}", result, total);
+        }
+    } else {
+        println!("{:?}", records.finish()?);
+    }
+    Ok(())
+}
+
+fn stream(day: u8, part: u8, verbose: bool) -> Option<io::Result<()>> {
+    let input = io::stdin().lock();
+    let result = match (day, part) {
+        (1, 1) => run(stream::lines(input, day1::CalibrationSum::part1()), verbose),
+        (1, 2) => run(stream::lines(input, day1::CalibrationSum::part2()), verbose),
+        (2, 1) => run(
+            stream::lines(input, day2::PossibleGames::default()),
+            verbose,
+        ),
+        (2, 2) => run(stream::lines(input, day2::PowerSum::default()), verbose),
+        (4, 1) => run(stream::lines(input, day4::Points::default()), verbose),
+        (4, 2) => run(stream::lines(input, day4::Copies::default()), verbose),
+        (7, 1) => run(stream::lines(input, day7::Winnings::part1()), verbose),
+        (7, 2) => run(stream::lines(input, day7::Winnings::part2()), verbose),
+        (9, 1) => run(stream::lines(input, day9::Extrapolation::part1()), verbose),
+        (9, 2) => run(stream::lines(input, day9::Extrapolation::part2()), verbose),
+        (15, 1) => run(
+            stream::split(input, b',', day15::HashSum::default()),
+            verbose,
+        ),
+        (15, 2) => run(stream::split(input, b',', day15::Boxes::default()), verbose),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn explain(day: u8, part: u8) -> Option<io::Result<String>> {
+    let explain: Box<dyn Fn(&str) -> String> = match (day, part) {
+        (1, 1) => lexicon(day1::DigitLexicon::digits()),
+        (1, 2) => lexicon(day1::DigitLexicon::english()),
+        (7, 1) => ruleset(day7::Ruleset::camel()),
+        (7, 2) => ruleset(day7::Ruleset::jokers()),
+        _ => return None,
+    };
+    let mut input = String::new();
+    Some(
+        io::stdin()
+            .read_to_string(&mut input)
+            .map(|_| explain(&input)),
+    )
+}
+
+fn lexicon(lexicon: day1::DigitLexicon) -> Box<dyn Fn(&str) -> String> {
+    Box::new(move |input| day1::explain(input, &lexicon))
+}
+
+fn ruleset(rules: day7::Ruleset) -> Box<dyn Fn(&str) -> String> {
+    Box::new(move |input| rules.explain(&day7::input_generator(input)))
+}
+
+// Overflows detected in a `checked` build are reported instead of crashing
+fn solve(
+    day: u8,
+    part: u8,
+    params: &[i64],
+) -> io::Result<Result<Option<String>, checked::Overflow>> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let input = input.trim_end_matches('\n');
+    Ok(checked::catch(|| registry::solve(day, part, input, params)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let verbose = args.iter().any(|a| a == "-v");
+    let args: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|&a| a != "-v")
+        .collect();
+    match args[..] {
+        ["stream", day, part] => {
+            let day: u8 = day.parse().unwrap_or_else(|_| fail(USAGE));
+            let part: u8 = part.parse().unwrap_or_else(|_| fail(USAGE));
+            match stream(day, part, verbose) {
+                Some(Ok(())) => {}
+                Some(Err(e)) => fail(&e.to_string()),
+                None => fail(&format!("day {} part {} cannot be streamed", day, part)),
+            }
+        }
+        ["explain", day, part] => {
+            let day: u8 = day.parse().unwrap_or_else(|_| fail(USAGE));
+            let part: u8 = part.parse().unwrap_or_else(|_| fail(USAGE));
+            match explain(day, part) {
+                Some(Ok(listing)) => print!("{}", listing),
+                Some(Err(e)) => fail(&e.to_string()),
+                None => fail(&format!("day {} part {} cannot be explained", day, part)),
+            }
+        }
+        ["solve", day, part, ref params @ ..] => {
+            let day: u8 = day.parse().unwrap_or_else(|_| fail(USAGE));
+            let part: u8 = part.parse().unwrap_or_else(|_| fail(USAGE));
+            let params: Vec<i64> = params
+                .iter()
+                .map(|p| p.parse().unwrap_or_else(|_| fail(USAGE)))
+                .collect();
+            match solve(day, part, &params) {
+                Ok(Ok(Some(answer))) => println!("{}", answer),
+                Ok(Ok(None)) => fail(&format!(
+                    "day {} part {} does not take {} parameters",
+                    day,
+                    part,
+                    params.len()
+                )),
+                Ok(Err(overflow)) => fail(&overflow.to_string()),
+                Err(e) => fail(&e.to_string()),
+            }
+        }
+        _ => fail(USAGE),
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    cell::Cell,
+    error::Error,
+    fmt,
+    panic::{self, UnwindSafe},
+};
+
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, One, Zero};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub day: u8,
+    pub op: &'static str,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {}: overflow in {}", self.day, self.op)
+    }
+}
+
+impl Error for Overflow {}
+
+thread_local! {
+    static LAST: Cell<Option<Overflow>> = const { Cell::new(None) };
+}
+
+// Solvers are plain functions, so an overflow unwinds out of them; `catch` turns
+// it back into an error
+fn overflow(day: u8, op: &'static str) -> ! {
+    let overflow = Overflow { day, op };
+    LAST.with(|last| last.set(Some(overflow)));
+    panic!("{}", overflow)
+}
+
+// Runs `f`, returning the overflow it hit, if any. Other panics are passed on.
+pub fn catch<T, F: FnOnce() -> T + UnwindSafe>(f: F) -> Result<T, Overflow> {
+    LAST.with(|last| last.set(None));
+    panic::catch_unwind(f).map_err(|payload| match LAST.with(Cell::take) {
+        Some(overflow) => overflow,
+        None => panic::resume_unwind(payload),
+    })
+}
+
+// Without the `checked` feature these compile down to the plain operators
+
+pub fn add<T: CheckedAdd>(day: u8, op: &'static str, a: T, b: T) -> T {
+    if cfg!(feature = "checked") {
+        a.checked_add(&b).unwrap_or_else(|| overflow(day, op))
+    } else {
+        a + b
+    }
+}
+
+pub fn sub<T: CheckedSub>(day: u8, op: &'static str, a: T, b: T) -> T {
+    if cfg!(feature = "checked") {
+        a.checked_sub(&b).unwrap_or_else(|| overflow(day, op))
+    } else {
+        a - b
+    }
+}
+
+pub fn mul<T: CheckedMul>(day: u8, op: &'static str, a: T, b: T) -> T {
+    if cfg!(feature = "checked") {
+        a.checked_mul(&b).unwrap_or_else(|| overflow(day, op))
+    } else {
+        a * b
+    }
+}
+
+pub fn sum<T, I>(day: u8, op: &'static str, xs: I) -> T
+where
+    T: CheckedAdd + Zero,
+    I: IntoIterator<Item = T>,
+{
+    xs.into_iter()
+        .fold(T::zero(), |acc, x| add(day, op, acc, x))
+}
+
+pub fn product<T, I>(day: u8, op: &'static str, xs: I) -> T
+where
+    T: CheckedMul + One,
+    I: IntoIterator<Item = T>,
+{
+    xs.into_iter().fold(T::one(), |acc, x| mul(day, op, acc, x))
+}
+
+// For arithmetic that is checked elsewhere and only says whether it overflowed. There is
+// no unchecked value to fall back on, so None is reported with or without the feature.
+pub fn expect<T>(day: u8, op: &'static str, x: Option<T>) -> T {
+    x.unwrap_or_else(|| overflow(day, op))
+}
+
+// For fixed-width state such as the bits of a u64: passes n on while it is at most max
+pub fn capacity(day: u8, op: &'static str, n: usize, max: usize) -> usize {
+    if cfg!(feature = "checked") && n > max {
+        overflow(day, op)
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_range() {
+        assert_eq!(add(1, "test", 2u8, 3), 5);
+        assert_eq!(sub(1, "test", 3u8, 2), 1);
+        assert_eq!(mul(1, "test", 16u8, 15), 240);
+        assert_eq!(sum(1, "test", [100u8, 100, 55]), 255);
+        assert_eq!(product(1, "test", [5u8, 3, 17]), 255);
+        assert_eq!(capacity(1, "test", 0, 64), 0);
+        assert_eq!(capacity(1, "test", 64, 64), 64);
+        assert_eq!(expect(1, "test", Some(7)), 7);
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    #[should_panic(expected = "day 24: overflow in test")]
+    fn reports_day_and_operation() {
+        mul(24, "test", i64::MAX, 2);
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    #[should_panic(expected = "day 20: overflow in test")]
+    fn over_capacity() {
+        capacity(20, "test", 65, 64);
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn caught() {
+        assert_eq!(
+            catch(|| add(24, "test", i64::MAX, 1)),
+            Err(Overflow {
+                day: 24,
+                op: "test"
+            })
+        );
+        assert_eq!(catch(|| add(24, "test", 1, 1)), Ok(2));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn caught_in_solver() {
+        // Pattern lines are packed into usize bits, so 70 columns cannot fit
+        let wide = ["#".repeat(70), ".".repeat(70)].join("\n");
+        assert_eq!(
+            catch(|| crate::registry::solve(13, 1, &wide, &[])),
+            Err(Overflow {
+                day: 13,
+                op: "line bits"
+            })
+        );
+    }
+
+    #[test]
+    fn expected_in_every_build() {
+        assert_eq!(
+            catch(|| expect::<u8>(9, "test", None)),
+            Err(Overflow { day: 9, op: "test" })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unrelated")]
+    fn other_panics_pass_through() {
+        let _ = catch(|| panic!("unrelated"));
+    }
+}
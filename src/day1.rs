@@ -1,40 +1,404 @@
-const DIGITS: &'static [&'static str] = &[
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{self, Write},
+    ops::Range,
+};
+
+use crate::{checked, stream::Accumulator};
+
+const DIGITS: &[&str] = &[
     "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
 ];
+const FRENCH: &[&str] = &[
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+];
+const GERMAN: &[&str] = &[
+    "null", "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+];
+const ROMAN: &[&str] = &["", "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX"];
 const RADIX: u32 = DIGITS.len() as u32;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Digit,
+    Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub span: Range<usize>,
+    pub kind: TokenKind,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    pub first: Token,
+    pub last: Token,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CalibrationError {
+    NoDigits(String),
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::NoDigits(line) => write!(f, "no digits in {:?}", line),
+        }
+    }
+}
+
+impl Error for CalibrationError {}
+
+// All tokens compiled into one byte-level Aho-Corasick automaton, with the
+// failure links folded into a full transition table
+pub struct DigitLexicon {
+    tokens: Vec<(String, u32)>,
+    delta: Vec<[u32; 256]>,
+    out: Vec<Vec<usize>>,
+}
+
+impl DigitLexicon {
+    pub fn new<S: AsRef<str>>(table: &[(S, u32)]) -> DigitLexicon {
+        let tokens: Vec<(String, u32)> = table
+            .iter()
+            .filter(|(t, _)| !t.as_ref().is_empty())
+            .map(|(t, v)| (t.as_ref().to_string(), *v))
+            .collect();
+
+        let mut children: Vec<HashMap<u8, usize>> = vec![HashMap::new()];
+        let mut out: Vec<Vec<usize>> = vec![Vec::new()];
+        for (i, (token, _)) in tokens.iter().enumerate() {
+            let mut state = 0;
+            for &b in token.as_bytes() {
+                state = match children[state].get(&b) {
+                    Some(&next) => next,
+                    None => {
+                        let next = children.len();
+                        children.push(HashMap::new());
+                        out.push(Vec::new());
+                        children[state].insert(b, next);
+                        next
+                    }
+                };
+            }
+            out[state].push(i);
+        }
+
+        let mut delta = vec![[0u32; 256]; children.len()];
+        let mut fail = vec![0; children.len()];
+        let mut work: VecDeque<usize> = VecDeque::new();
+        for (&b, &next) in children[0].iter() {
+            delta[0][b as usize] = next as u32;
+            work.push_back(next);
+        }
+        while let Some(state) = work.pop_front() {
+            let inherited = out[fail[state]].clone();
+            out[state].extend(inherited);
+            delta[state] = delta[fail[state]];
+            for (&b, &next) in children[state].iter() {
+                fail[next] = delta[fail[state]][b as usize] as usize;
+                delta[state][b as usize] = next as u32;
+                work.push_back(next);
+            }
+        }
+
+        DigitLexicon { tokens, delta, out }
+    }
+
+    // One `token value` pair per line
+    pub fn parse(table: &str) -> DigitLexicon {
+        let entries: Vec<(&str, u32)> = table
+            .lines()
+            .filter_map(|line| {
+                let (token, value) = line.trim().rsplit_once(char::is_whitespace)?;
+                Some((token.trim(), value.parse().ok()?))
+            })
+            .collect();
+        DigitLexicon::new(&entries)
+    }
+
+    fn words(words: &[&str]) -> DigitLexicon {
+        let digits = (0..RADIX).map(|d| (char::from_digit(d, RADIX).unwrap().to_string(), d));
+        let words = words
+            .iter()
+            .enumerate()
+            .map(|(d, w)| (w.to_string(), d as u32));
+        DigitLexicon::new(&digits.chain(words).collect::<Vec<_>>())
+    }
+
+    pub fn digits() -> DigitLexicon {
+        DigitLexicon::words(&[])
+    }
+
+    pub fn english() -> DigitLexicon {
+        DigitLexicon::words(DIGITS)
+    }
+
+    pub fn french() -> DigitLexicon {
+        DigitLexicon::words(FRENCH)
+    }
+
+    pub fn german() -> DigitLexicon {
+        DigitLexicon::words(GERMAN)
+    }
+
+    pub fn roman() -> DigitLexicon {
+        DigitLexicon::words(ROMAN)
+    }
+
+    // First is the earliest-starting token and last the latest-ending one, the
+    // longest winning ties, so overlaps like "twone" give two and one
+    pub fn first_last(&self, line: &str) -> Option<(Token, Token)> {
+        let mut first: Option<(Range<usize>, usize)> = None;
+        let mut last: Option<(Range<usize>, usize)> = None;
+        let mut state = 0;
+        for (i, &b) in line.as_bytes().iter().enumerate() {
+            state = self.delta[state][b as usize] as usize;
+            for &t in self.out[state].iter() {
+                let span = i + 1 - self.tokens[t].0.len()..i + 1;
+                if first.as_ref().is_none_or(|(f, _)| {
+                    span.start < f.start || (span.start == f.start && span.end > f.end)
+                }) {
+                    first = Some((span.clone(), t));
+                }
+                if last.as_ref().is_none_or(|(l, _)| {
+                    span.end > l.end || (span.end == l.end && span.start < l.start)
+                }) {
+                    last = Some((span, t));
+                }
+            }
+        }
+        let token = |(span, t): (Range<usize>, usize)| {
+            let (text, value) = &self.tokens[t];
+            let kind = if text.len() == 1 && text.as_bytes()[0].is_ascii_digit() {
+                TokenKind::Digit
+            } else {
+                TokenKind::Word
+            };
+            Token {
+                span,
+                kind,
+                value: *value,
+            }
+        };
+        Some((token(first?), token(last?)))
+    }
+
+    pub fn calibrate_line(&self, line: &str) -> Result<Calibration, CalibrationError> {
+        let (first, last) = self
+            .first_last(line)
+            .ok_or_else(|| CalibrationError::NoDigits(line.to_string()))?;
+        let value = checked::add(
+            1,
+            "calibration value",
+            checked::mul(1, "calibration value", RADIX, first.value),
+            last.value,
+        );
+        Ok(Calibration { first, last, value })
+    }
+
+    pub fn value(&self, line: &str) -> Option<u32> {
+        self.calibrate_line(line).ok().map(|c| c.value)
+    }
+}
+
+fn calibrate(input: &str, lexicon: &DigitLexicon) -> u32 {
+    checked::sum(
+        1,
+        "sum of calibration values",
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, s)| match lexicon.calibrate_line(s) {
+                Ok(c) => c.value,
+                Err(e) => panic!("line {}: {}", i + 1, e),
+            }),
+    )
+}
+
+fn describe(line: &str, token: &Token) -> String {
+    let kind = match token.kind {
+        TokenKind::Digit => "digit",
+        TokenKind::Word => "word",
+    };
+    format!(
+        "{:?} ({} at {}..{})",
+        &line[token.span.clone()],
+        kind,
+        token.span.start,
+        token.span.end
+    )
+}
+
+// Each line is followed by carets under its first and last token
+pub fn explain(input: &str, lexicon: &DigitLexicon) -> String {
+    let mut out = String::new();
+    let mut total = 0;
+    let mut errors = 0;
+    for (i, line) in input.lines().enumerate() {
+        writeln!(out, "{:>5} | {}", i + 1, line).unwrap();
+        match lexicon.calibrate_line(line) {
+            Ok(c) => {
+                let marks: String = line
+                    .char_indices()
+                    .map(|(j, _)| {
+                        if c.first.span.contains(&j) || c.last.span.contains(&j) {
+                            '^'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect();
+                writeln!(
+                    out,
+                    "      | {} {} + {} = {}",
+                    marks.trim_end(),
+                    describe(line, &c.first),
+                    describe(line, &c.last),
+                    c.value
+                )
+                .unwrap();
+                total = checked::add(1, "sum of calibration values", total, c.value);
+            }
+            Err(e) => {
+                writeln!(out, "      | error: {}", e).unwrap();
+                errors += 1;
+            }
+        }
+    }
+    writeln!(out, "total {} ({} lines without digits)", total, errors).unwrap();
+    out
+}
+
 #[aoc(day1, part1)]
 pub fn solve_part1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|s| {
-            let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(RADIX)).collect();
-            RADIX * digits.first().unwrap() + digits.last().unwrap()
-        })
-        .sum()
+    calibrate(input, &DigitLexicon::digits())
 }
 
 #[aoc(day1, part2)]
 pub fn solve_part2(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|s| {
-            let mut digits: Vec<(usize, u32)> = s
-                .chars()
-                .enumerate()
-                .filter_map(|(i, c)| c.to_digit(RADIX).map(|d| (i, d)))
-                .collect();
-            let mut words: Vec<(usize, u32)> = DIGITS
-                .iter()
-                .enumerate()
-                .flat_map(|(d, w)| [(s.find(w), d), (s.rfind(w), d)])
-                .filter_map(|(p, d)| p.map(|x| (x, d as u32)))
-                .collect();
-            digits.append(&mut words);
-            digits.sort();
-            let (_, f) = digits.first().unwrap();
-            let (_, l) = digits.last().unwrap();
-            RADIX * f + l
-        })
-        .sum()
+    calibrate(input, &DigitLexicon::english())
+}
+
+pub struct CalibrationSum {
+    lexicon: DigitLexicon,
+    total: u32,
+}
+
+impl CalibrationSum {
+    pub fn new(lexicon: DigitLexicon) -> CalibrationSum {
+        CalibrationSum { lexicon, total: 0 }
+    }
+
+    pub fn part1() -> CalibrationSum {
+        CalibrationSum::new(DigitLexicon::digits())
+    }
+
+    pub fn part2() -> CalibrationSum {
+        CalibrationSum::new(DigitLexicon::english())
+    }
+}
+
+// Lines without digits come back as errors and do not count towards the total
+impl Accumulator for CalibrationSum {
+    type Record = Result<u32, CalibrationError>;
+    type Total = u32;
+
+    fn feed(&mut self, line: &str) -> Result<u32, CalibrationError> {
+        let value = self.lexicon.calibrate_line(line)?.value;
+        self.total = checked::add(1, "sum of calibration values", self.total, value);
+        Ok(value)
+    }
+
+    fn total(&self) -> u32 {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry::fixture, stream};
+
+    #[test]
+    fn streaming() {
+        let input = fixture(1, "spelled");
+        let records: Vec<(u32, u32)> = stream::lines(input.as_bytes(), CalibrationSum::part2())
+            .map(|r| {
+                let (value, total) = r.unwrap();
+                (value.unwrap(), total)
+            })
+            .collect();
+        assert_eq!(records[..3], [(29, 29), (83, 112), (13, 125)]);
+        assert_eq!(records.last(), Some(&(76, 281)));
+
+        let mut digits = stream::lines("a1b\nnone\n22".as_bytes(), CalibrationSum::part1());
+        assert_eq!(digits.nth(1).unwrap().unwrap().1, 11);
+        assert_eq!(digits.finish().unwrap(), 33);
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let english = DigitLexicon::english();
+        let (first, last) = english.first_last("xtwone").unwrap();
+        assert_eq!((first.span, first.value), (1..4, 2));
+        assert_eq!((last.span, last.value), (3..6, 1));
+        assert_eq!(english.value("eightwo"), Some(82));
+        assert_eq!(english.value("oneight"), Some(18));
+        assert_eq!(english.value("sevenine"), Some(79));
+        assert_eq!(english.value("nothing"), None);
+    }
+
+    #[test]
+    fn other_languages() {
+        assert_eq!(DigitLexicon::french().value("aquatreuxcinqdeux"), Some(42));
+        assert_eq!(DigitLexicon::german().value("fünfzehnsiebenull"), Some(50));
+        assert_eq!(DigitLexicon::roman().value("xVIIIyIX"), Some(89));
+        assert_eq!(DigitLexicon::roman().value("XIV"), Some(44));
+    }
+
+    #[test]
+    fn custom_tokens() {
+        let lexicon = DigitLexicon::parse("dozen 12\nbaker's dozen 13\n  nil 0\nbogus");
+        assert_eq!(lexicon.value("a baker's dozen or nil"), Some(130));
+        assert_eq!(lexicon.first_last("dozen").unwrap().0.value, 12);
+        assert_eq!(lexicon.value("nada"), None);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let english = DigitLexicon::english();
+        let c = english.calibrate_line("xtwone3four").unwrap();
+        assert_eq!(c.first.kind, TokenKind::Word);
+        assert_eq!(c.first.span, 1..4);
+        assert_eq!((c.last.span, c.last.kind), (7..11, TokenKind::Word));
+        assert_eq!(c.value, 24);
+
+        let c = DigitLexicon::digits().calibrate_line("treb7uchet").unwrap();
+        assert_eq!(c.first, c.last);
+        assert_eq!(c.first.kind, TokenKind::Digit);
+        assert_eq!(c.value, 77);
+
+        assert_eq!(
+            english.calibrate_line("pqrstu"),
+            Err(CalibrationError::NoDigits("pqrstu".to_string()))
+        );
+    }
+
+    #[test]
+    fn annotated_listing() {
+        let listing = explain("xtwone3four\nnope", &DigitLexicon::english());
+        let expected = r#"    1 | xtwone3four
+      |  ^^^   ^^^^ "two" (word at 1..4) + "four" (word at 7..11) = 24
+    2 | nope
+      | error: no digits in "nope"
+total 24 (1 lines without digits)
+"#;
+        assert_eq!(listing, expected);
+    }
 }
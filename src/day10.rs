@@ -2,6 +2,8 @@ use std::collections::{HashMap, VecDeque};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Coords = (i32, i32);
 type Task = (usize, usize, HashMap<Coords, char>);
 
@@ -36,7 +38,7 @@ fn try_schedule(
     work: &mut VecDeque<(Coords, usize)>,
 ) {
     if map.get(&coords).map_or(false, |&c| filter.contains(c)) {
-        work.push_back((coords, d + 1));
+        work.push_back((coords, checked::add(10, "loop distance", d, 1)));
     }
 }
 
@@ -108,14 +110,14 @@ fn dijkstra_loop(map: &HashMap<Coords, char>) -> HashMap<Coords, usize> {
 }
 
 #[aoc(day10, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let (_, _, map) = input;
     let dist = dijkstra_loop(map);
     *dist.values().max().unwrap()
 }
 
 #[aoc(day10, part2)]
-fn solve_part2(input: &Task) -> usize {
+pub fn solve_part2(input: &Task) -> usize {
     let (h, w, map) = input;
     let dist = dijkstra_loop(map);
     let mut count = 0;
@@ -128,108 +130,9 @@ fn solve_part2(input: &Task) -> usize {
                 inside = !inside;
             }
             if !on_line && inside {
-                count += 1;
+                count = checked::add(10, "enclosed tiles", count, 1);
             }
         }
     }
     count
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-.....
-.S-7.
-.|.|.
-.L-J.
-....."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 4);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 1);
-    }
-
-    #[test]
-    fn example2() {
-        let input = r#"
-..F7.
-.FJ|.
-SJ.L7
-|F--J
-LJ..."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 8);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 1);
-    }
-
-    #[test]
-    fn example3() {
-        let input = r#"
-...........
-.S-------7.
-.|F-----7|.
-.||.....||.
-.||.....||.
-.|L-7.F-J|.
-.|..|.|..|.
-.L--J.L--J.
-..........."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 23);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 4);
-    }
-
-    #[test]
-    fn example4() {
-        let input = r#"
-.F----7F7F7F7F-7....
-.|F--7||||||||FJ....
-.||.FJ||||||||L7....
-FJL7L7LJLJ||LJ.L-7..
-L--J.L7...LJS7F-7L7.
-....F-J..F7FJ|L7L7L7
-....L7.F7||L7|.L7L7|
-.....|FJLJ|FJ|F7|.LJ
-....FJL-7.||.||||...
-....L---J.LJ.LJLJ..."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 70);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 8);
-    }
-
-    #[test]
-    fn example5() {
-        let input = r#"
-FF7FSF7F7F7F7F7F---7
-L|LJ||||||||||||F--J
-FL-7LJLJ||||||LJL-77
-F--JF--7||LJLJ7F7FJ-
-L---JF-JLJ.||-FJLJJ7
-|F|F-JF---7F7-L7L|7|
-|FFJF7L7F-JF7|JL---7
-7-L-JL7||F7|L7F-7F7|
-L.L7LFJ|||||FJL7||LJ
-L7JLJL-JLJLJL--JLJ.L"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 80);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 10);
-    }
-}
@@ -1,5 +1,7 @@
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Task = Vec<Vec<bool>>;
 
 #[aoc_generator(day11)]
@@ -10,7 +12,7 @@ pub fn input_generator(input: &str) -> Task {
     p.parse(input).unwrap()
 }
 
-fn solve(input: &Task, scale: i64) -> i64 {
+pub fn solve(input: &Task, scale: i64) -> i64 {
     let galaxies: Vec<(usize, usize)> = input
         .iter()
         .enumerate()
@@ -20,21 +22,22 @@ fn solve(input: &Task, scale: i64) -> i64 {
                 .filter_map(move |(j, &c)| if c { Some((i, j)) } else { None })
         })
         .collect();
+    let gap = checked::sub(11, "expansion", scale, 1);
     let mut dy = vec![0; input.len()];
     let mut corr: i64 = 0;
     for i in 0..dy.len() {
         if input[i].iter().all(|c| !c) {
-            corr += scale - 1;
+            corr = checked::add(11, "row expansion", corr, gap);
         }
-        dy[i] = corr + i as i64;
+        dy[i] = checked::add(11, "row expansion", corr, i as i64);
     }
     let mut dx = vec![0; input[0].len()];
     corr = 0;
     for j in 0..dx.len() {
         if input.iter().all(|row| !row[j]) {
-            corr += scale - 1;
+            corr = checked::add(11, "column expansion", corr, gap);
         }
-        dx[j] = corr + j as i64;
+        dx[j] = checked::add(11, "column expansion", corr, j as i64);
     }
     let mut result = 0;
     for i in 0..galaxies.len() {
@@ -46,8 +49,13 @@ fn solve(input: &Task, scale: i64) -> i64 {
             let x21 = dx[x2];
             let y21 = dy[y2];
 
-            let d = (x21 - x11).abs() + (y21 - y11).abs();
-            result += d;
+            let d = checked::add(
+                11,
+                "distance",
+                checked::sub(11, "distance", x21, x11).abs(),
+                checked::sub(11, "distance", y21, y11).abs(),
+            );
+            result = checked::add(11, "sum of distances", result, d);
         }
     }
 
@@ -55,39 +63,11 @@ fn solve(input: &Task, scale: i64) -> i64 {
 }
 
 #[aoc(day11, part1)]
-fn solve_part1(input: &Task) -> i64 {
+pub fn solve_part1(input: &Task) -> i64 {
     solve(input, 2)
 }
 
 #[aoc(day11, part2)]
-fn solve_part2(input: &Task) -> i64 {
+pub fn solve_part2(input: &Task) -> i64 {
     solve(input, 1_000_000)
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-...#......
-.......#..
-#.........
-..........
-......#...
-.#........
-.........#
-..........
-.......#..
-#...#....."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve(&parsed, 2);
-        assert_eq!(result1, 374);
-        let result2 = solve(&parsed, 10);
-        assert_eq!(result2, 1030);
-        let result3 = solve(&parsed, 100);
-        assert_eq!(result3, 8410);
-    }
-}
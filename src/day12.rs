@@ -2,6 +2,8 @@ use std::{collections::HashSet, iter::repeat};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Task = Vec<(String, Vec<usize>)>;
 
 #[aoc_generator(day12)]
@@ -83,7 +85,7 @@ fn arrangements(cs: &[char], jp: &[usize]) -> u64 {
                     } else {
                         0
                     };
-                    dp[i][j] = consume_nr + dp[i][j - 1];
+                    dp[i][j] = checked::add(12, "arrangements", consume_nr, dp[i][j - 1]);
                 }
                 _ => panic!("Unknown character {}", c),
             }
@@ -93,13 +95,21 @@ fn arrangements(cs: &[char], jp: &[usize]) -> u64 {
 }
 
 #[aoc(day12, part1)]
-fn solve_part1(input: &Task) -> u64 {
-    input.iter().map(|(s, jp)| arr(s, jp)).sum()
+pub fn solve_part1(input: &Task) -> u64 {
+    checked::sum(
+        12,
+        "total arrangements",
+        input.iter().map(|(s, jp)| arr(s, jp)),
+    )
 }
 
 #[aoc(day12, part2)]
-fn solve_part2(input: &Task) -> u64 {
-    input.iter().map(|(s, jp)| arr2(s, jp)).sum()
+pub fn solve_part2(input: &Task) -> u64 {
+    checked::sum(
+        12,
+        "total arrangements",
+        input.iter().map(|(s, jp)| arr2(s, jp)),
+    )
 }
 
 #[cfg(test)]
@@ -125,21 +135,4 @@ mod tests {
         assert_eq!(arr2("????.######..#####.", &vec![1, 6, 5]), 2500);
         assert_eq!(arr2("?###????????", &vec![3, 2, 1]), 506250);
     }
-
-    #[test]
-    fn example1() {
-        let input = r#"
-???.### 1,1,3
-.??..??...?##. 1,1,3
-?#?#?#?#?#?#?#? 1,3,1,6
-????.#...#... 4,1,1
-????.######..#####. 1,6,5
-?###???????? 3,2,1"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 21);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 525152);
-    }
 }
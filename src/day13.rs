@@ -1,5 +1,7 @@
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Task = Vec<Vec<Vec<usize>>>;
 
 #[aoc_generator(day13)]
@@ -37,12 +39,13 @@ fn sum_1d(xs: &Vec<usize>, diff: usize) -> usize {
 }
 
 fn summarise_lines(m: &Vec<Vec<usize>>) -> (Vec<usize>, Vec<usize>) {
+    let push = |acc, c| checked::add(13, "line bits", checked::mul(13, "line bits", 2, acc), c);
     let hor: Vec<usize> = m
         .iter()
-        .map(|s| s.iter().fold(0, |acc, c| 2 * acc + c))
+        .map(|s| s.iter().fold(0, |acc, &c| push(acc, c)))
         .collect();
     let ver: Vec<usize> = (0..m[0].len())
-        .map(|j| m.iter().fold(0, |acc, s| 2 * acc + s[j]))
+        .map(|j| m.iter().fold(0, |acc, s| push(acc, s[j])))
         .collect();
     (hor, ver)
 }
@@ -50,46 +53,16 @@ fn summarise_lines(m: &Vec<Vec<usize>>) -> (Vec<usize>, Vec<usize>) {
 fn sum(m: &Vec<Vec<usize>>, diff: usize) -> usize {
     let (hor, ver) = summarise_lines(m);
 
-    sum_1d(&hor, diff) * 100 + sum_1d(&ver, diff)
+    let rows = checked::mul(13, "summary", sum_1d(&hor, diff), 100);
+    checked::add(13, "summary", rows, sum_1d(&ver, diff))
 }
 
 #[aoc(day13, part1)]
-fn solve_part1(input: &Task) -> usize {
-    input.iter().map(|m| sum(m, 0)).sum()
+pub fn solve_part1(input: &Task) -> usize {
+    checked::sum(13, "sum of summaries", input.iter().map(|m| sum(m, 0)))
 }
 
 #[aoc(day13, part2)]
-fn solve_part2(input: &Task) -> usize {
-    input.iter().map(|m| sum(m, 1)).sum()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-#.##..##.
-..#.##.#.
-##......#
-##......#
-..#.##.#.
-..##..##.
-#.#.##.#.
-
-#...##..#
-#....#..#
-..##..###
-#####.##.
-#####.##.
-..##..###
-#....#..#"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 405);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 400);
-    }
+pub fn solve_part2(input: &Task) -> usize {
+    checked::sum(13, "sum of summaries", input.iter().map(|m| sum(m, 1)))
 }
@@ -2,6 +2,8 @@ use std::{collections::HashMap, mem::swap};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Task = Vec<Vec<usize>>;
 
 #[aoc_generator(day14)]
@@ -18,7 +20,7 @@ fn load(state: &Task) -> usize {
     for (i, row) in state.iter().enumerate() {
         for (_, c) in row.iter().enumerate() {
             if *c == 2 {
-                weight += n - i;
+                weight = checked::add(14, "load", weight, n - i);
             }
         }
     }
@@ -63,7 +65,7 @@ fn step(input: &Task, work: &mut Task, h: i32, w: i32, di: i32, dj: i32) {
 }
 
 #[aoc(day14, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let mut work: Vec<Vec<usize>> = input.into_iter().map(|r| vec![0; r.len()]).collect();
     step(
         input,
@@ -89,7 +91,7 @@ fn hash(state: &Task) -> usize {
 }
 
 #[aoc(day14, part2)]
-fn solve_part2(input: &Task) -> usize {
+pub fn solve_part2(input: &Task) -> usize {
     let n = 1000000000;
     let h = input.len() as i32;
     let w = input[0].len() as i32;
@@ -119,29 +121,3 @@ fn solve_part2(input: &Task) -> usize {
     }
     load(&state)
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-O....#....
-O.OO#....#
-.....##...
-OO.#O....O
-.O.....O#.
-O.#..O.#.#
-..O..#O..O
-.......O..
-#....###..
-#OO..#...."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 136);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 64);
-    }
-}
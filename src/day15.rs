@@ -1,5 +1,7 @@
 use aoc_parse::{parser, prelude::*};
 
+use crate::{checked, stream::Accumulator};
+
 fn hash(s: &str) -> usize {
     let mut res: usize = 0;
     for &c in s.as_bytes() {
@@ -11,8 +13,12 @@ fn hash(s: &str) -> usize {
 }
 
 #[aoc(day15, part1)]
-fn solve_part1(input: &str) -> usize {
-    input.trim().split(",").map(|s| hash(s)).sum()
+pub fn solve_part1(input: &str) -> usize {
+    checked::sum(
+        15,
+        "sum of hashes",
+        input.trim().split(",").map(|s| hash(s)),
+    )
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -31,45 +37,107 @@ fn parse(s: &str) -> Cmd {
 }
 
 #[aoc(day15, part2)]
-fn solve_part2(input: &str) -> usize {
-    let program: Vec<Cmd> = input.trim().split(",").map(|s| parse(s)).collect();
-    let mut hm: Vec<Vec<(String, usize)>> = (0..256).map(|_| Vec::new()).collect();
-    for cmd in program {
-        match cmd {
+pub fn solve_part2(input: &str) -> usize {
+    let mut boxes = Boxes::default();
+    for s in input.trim().split(",") {
+        boxes.feed(s);
+    }
+    boxes.total()
+}
+
+#[derive(Default)]
+pub struct HashSum {
+    total: usize,
+}
+
+impl Accumulator for HashSum {
+    type Record = usize;
+    type Total = usize;
+
+    fn feed(&mut self, step: &str) -> usize {
+        let h = hash(step);
+        self.total = checked::add(15, "sum of hashes", self.total, h);
+        h
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+// Focusing power is updated per step, so the total never needs a full rescan
+fn power(h: usize, slot: usize, value: usize) -> usize {
+    checked::product(15, "focusing power", [h + 1, slot + 1, value])
+}
+
+pub struct Boxes {
+    hm: Vec<Vec<(String, usize)>>,
+    total: usize,
+}
+
+impl Default for Boxes {
+    fn default() -> Boxes {
+        Boxes {
+            hm: (0..256).map(|_| Vec::new()).collect(),
+            total: 0,
+        }
+    }
+}
+
+impl Accumulator for Boxes {
+    type Record = usize;
+    type Total = usize;
+
+    // Yields the box the step touched
+    fn feed(&mut self, step: &str) -> usize {
+        match parse(step) {
             Cmd::Rm(name) => {
                 let h = hash(&name);
-                let i = hm[h].iter().position(|(s, _)| *s == name);
-                match i {
-                    Some(ix) => {
-                        hm[h].remove(ix);
-                    }
-                    None => {}
+                let i = self.hm[h].iter().position(|(s, _)| *s == name);
+                if let Some(ix) = i {
+                    let (_, v) = self.hm[h].remove(ix);
+                    let shifted = checked::sum(
+                        15,
+                        "focusing power",
+                        self.hm[h][ix..].iter().map(|(_, v)| *v),
+                    );
+                    let lost = checked::mul(15, "focusing power", h + 1, shifted);
+                    self.total -= checked::add(15, "focusing power", power(h, ix, v), lost);
                 }
+                h
             }
             Cmd::Put(name, value) => {
                 let h = hash(&name);
-                let i = hm[h].iter().position(|(s, _)| *s == name);
+                let i = self.hm[h].iter().position(|(s, _)| *s == name);
                 match i {
-                    Some(ix) => hm[h][ix] = (name, value),
-                    None => hm[h].push((name, value)),
+                    Some(ix) => {
+                        let (_, old) = self.hm[h][ix];
+                        self.total -= power(h, ix, old);
+                        self.total =
+                            checked::add(15, "focusing power", self.total, power(h, ix, value));
+                        self.hm[h][ix] = (name, value);
+                    }
+                    None => {
+                        self.hm[h].push((name, value));
+                        let slot = self.hm[h].len() - 1;
+                        self.total =
+                            checked::add(15, "focusing power", self.total, power(h, slot, value));
+                    }
                 }
+                h
             }
         }
     }
-    hm.iter()
-        .enumerate()
-        .flat_map(|(i, bucket)| {
-            bucket
-                .iter()
-                .enumerate()
-                .map(move |(j, (_, v))| (i + 1) * (j + 1) * v)
-        })
-        .sum()
+
+    fn total(&self) -> usize {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{registry::fixture, stream};
 
     #[test]
     fn example_hash() {
@@ -77,11 +145,14 @@ mod tests {
     }
 
     #[test]
-    fn example1() {
-        let input = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7\n\n";
-        let result1 = solve_part1(&input);
-        assert_eq!(result1, 1320);
-        let result2 = solve_part2(&input);
-        assert_eq!(result2, 145);
+    fn streaming() {
+        let input = fixture(15, "example");
+        let hashes = stream::split(input.as_bytes(), b',', HashSum::default());
+        assert_eq!(hashes.finish().unwrap(), 1320);
+        let powers: Vec<usize> = stream::split(input.as_bytes(), b',', Boxes::default())
+            .map(|r| r.unwrap().1)
+            .collect();
+        assert_eq!(powers[..3], [1, 1, 1 + 3 * 2]);
+        assert_eq!(powers.last(), Some(&145));
     }
 }
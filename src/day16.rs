@@ -3,6 +3,8 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 type Coords = (i32, i32);
 
 #[derive(Clone, Copy)]
@@ -92,41 +94,17 @@ fn eval(input: &Task, seed: Particle) -> usize {
 }
 
 #[aoc(day16, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let seed = ((0, 0), (0, 1));
     eval(input, seed)
 }
 
 #[aoc(day16, part2)]
-fn solve_part2(input: &Task) -> usize {
-    let mut seeds: Vec<Particle> = Vec::with_capacity(2 * (input.h + input.w) as usize);
+pub fn solve_part2(input: &Task) -> usize {
+    let border = checked::add(16, "border", input.h, input.w);
+    let mut seeds: Vec<Particle> =
+        Vec::with_capacity(checked::mul(16, "border", 2, border) as usize);
     seeds.extend((0..input.h).flat_map(|i| [((i, 0), (0, 1)), ((i, input.w - 1), (0, -1))]));
     seeds.extend((0..input.w).flat_map(|j| [((0, j), (1, 0)), ((input.h - 1, j), (-1, 0))]));
     seeds.par_iter().map(|&p| eval(input, p)).max().unwrap()
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-.|...\....
-|.-.\.....
-.....|-...
-........|.
-..........
-.........\
-..../.\\..
-.-.-/..|..
-.|....-|.\
-..//.|...."#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 46);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 51);
-    }
-}
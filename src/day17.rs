@@ -3,6 +3,8 @@ use std::collections::HashMap;
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 #[derive(Eq, PartialEq, Hash, Debug, Copy, Clone)]
 pub enum Dir {
     N,
@@ -32,7 +34,7 @@ fn try_schedule(
     hl: i64,
 ) {
     let x = dist.entry((i, j, dir, b)).or_insert(i64::MIN);
-    let proposed_hl = hl - m[i][j] as i64;
+    let proposed_hl = checked::sub(17, "heat loss", hl, m[i][j] as i64);
     if *x >= proposed_hl {
         return;
     }
@@ -115,44 +117,15 @@ fn solve(input: &Task, max_fwd: usize, min_fwd: usize) -> i64 {
             }
         }
     }
-    -hl
+    checked::sub(17, "heat loss", 0, hl)
 }
 
 #[aoc(day17, part1)]
-fn solve_part1(input: &Task) -> i64 {
+pub fn solve_part1(input: &Task) -> i64 {
     solve(input, 3, 1)
 }
 
 #[aoc(day17, part2)]
-fn solve_part2(input: &Task) -> i64 {
+pub fn solve_part2(input: &Task) -> i64 {
     solve(input, 10, 4)
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-2413432311323
-3215453535623
-3255245654254
-3446585845452
-4546657867536
-1438598798454
-4457876987766
-3637877979653
-4654967986887
-4564679986453
-1224686865563
-2546548887735
-4322674655533"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 102);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 94);
-    }
-}
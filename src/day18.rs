@@ -1,5 +1,7 @@
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 pub struct Cmd {
     dir: usize,
     len: usize,
@@ -30,52 +32,31 @@ where
     for (dir, len) in cmds {
         let len = len as i64;
         let (di, dj) = MOVES[dir];
-        let next = (prev.0 + di * len, prev.1 + dj * len);
-        area += (prev.0 - next.0) * (prev.1 + next.1);
-        perimeter += len;
+        let next = (
+            checked::add(18, "trench", prev.0, checked::mul(18, "trench", di, len)),
+            checked::add(18, "trench", prev.1, checked::mul(18, "trench", dj, len)),
+        );
+        let cross = checked::mul(
+            18,
+            "shoelace",
+            checked::sub(18, "shoelace", prev.0, next.0),
+            checked::add(18, "shoelace", prev.1, next.1),
+        );
+        area = checked::add(18, "shoelace", area, cross);
+        perimeter = checked::add(18, "perimeter", perimeter, len);
         prev = next;
     }
-    (area.abs() + perimeter) / 2 + 1
+    checked::add(18, "lagoon", area.abs(), perimeter) / 2 + 1
 }
 
 #[aoc(day18, part1)]
-fn solve_part1(input: &Task) -> i64 {
+pub fn solve_part1(input: &Task) -> i64 {
     let cmds = input.iter().map(|cmd| (cmd.dir, cmd.len));
     area(cmds)
 }
 
 #[aoc(day18, part2)]
-fn solve_part2(input: &Task) -> i64 {
+pub fn solve_part2(input: &Task) -> i64 {
     let cmds = input.iter().map(|cmd| (cmd.rgb % 16, cmd.rgb / 16));
     area(cmds)
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-R 6 (#70c710)
-D 5 (#0dc571)
-L 2 (#5713f0)
-D 2 (#d2c081)
-R 2 (#59c680)
-D 2 (#411b91)
-L 5 (#8ceee2)
-U 2 (#caa173)
-L 1 (#1b58a2)
-U 2 (#caa171)
-R 2 (#7807d2)
-U 3 (#a77fa3)
-L 2 (#015232)
-U 2 (#7a21e3)"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 62);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 952408144115);
-    }
-}
@@ -2,6 +2,8 @@ use std::{collections::HashMap, ops::Range};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 #[derive(Debug, Clone)]
 pub enum Rule {
     Gt(usize, usize, String),
@@ -62,13 +64,13 @@ fn accepted(wfs: &HashMap<String, Vec<Rule>>, wf: &str, part: &[usize]) -> bool
 }
 
 #[aoc(day19, part1)]
-fn solve_part1(input: &Task) -> i64 {
-    input
+pub fn solve_part1(input: &Task) -> i64 {
+    let ratings = input
         .parts
         .iter()
         .filter(|&p| accepted(&input.workflows, "in", p))
-        .flat_map(|p| p.iter().map(|&x| x as i64))
-        .sum()
+        .flat_map(|p| p.iter().map(|&x| x as i64));
+    checked::sum(19, "sum of ratings", ratings)
 }
 
 type Parts = [Range<usize>; 4];
@@ -102,7 +104,7 @@ fn cut_at(p: Parts, name: usize, lim: usize) -> (Option<Parts>, Option<Parts>) {
 }
 
 fn size(p: &[Range<usize>]) -> i64 {
-    p.iter().map(|x| x.len() as i64).product()
+    checked::product(19, "combinations", p.iter().map(|x| x.len() as i64))
 }
 
 fn collect_accepted(
@@ -158,42 +160,9 @@ fn collect_accepted(
 }
 
 #[aoc(day19, part2)]
-fn solve_part2(input: &Task) -> i64 {
+pub fn solve_part2(input: &Task) -> i64 {
     let mut accepted: Vec<Parts> = Vec::new();
     let seed = [1..4001, 1..4001, 1..4001, 1..4001];
     collect_accepted(&input.workflows, "in", &seed, &mut accepted);
-    accepted.iter().map(|rs| size(rs)).sum()
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-px{a<2006:qkq,m>2090:A,rfg}
-pv{a>1716:R,A}
-lnx{m>1548:A,A}
-rfg{s<537:gd,x>2440:R,A}
-qs{s>3448:A,lnx}
-qkq{x<1416:A,crn}
-crn{x>2662:A,R}
-in{s<1351:px,qqz}
-qqz{s>2770:qs,m<1801:hdj,R}
-gd{a>3333:R,R}
-hdj{m>838:A,pv}
-
-{x=787,m=2655,a=1222,s=2876}
-{x=1679,m=44,a=2067,s=496}
-{x=2036,m=264,a=79,s=2244}
-{x=2461,m=1339,a=466,s=291}
-{x=2127,m=1623,a=2188,s=1013}"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 19114);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 167409079868000);
-    }
+    checked::sum(19, "combinations", accepted.iter().map(|rs| size(rs)))
 }
@@ -1,7 +1,12 @@
-use std::{cmp::max, collections::HashMap};
+use std::{
+    cmp::{max, Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::{checked, stream::Accumulator};
+
 pub struct Ball {
     colour: String,
     count: usize,
@@ -12,52 +17,487 @@ pub struct Game {
     rounds: Vec<Vec<Ball>>,
 }
 
-#[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Game> {
-    let p = parser!(lines(
+fn parse_game(line: &str) -> Game {
+    let p = parser!(
         "Game " nr:usize ": "
         rounds:repeat_sep(
             balls:repeat_sep(
                 count:usize " " colour:string(alpha*) => Ball { colour, count },
                 ", "),
             "; ") => Game { nr, rounds }
-    ));
-    p.parse(input).unwrap()
+    );
+    p.parse(line).unwrap()
+}
+
+#[aoc_generator(day2)]
+pub fn input_generator(input: &str) -> Vec<Game> {
+    input.lines().map(parse_game).collect()
 }
 
-fn possible(game: &Game, budget: &HashMap<&str, usize>) -> bool {
+pub type Budget = BTreeMap<String, usize>;
+
+fn allowance(budget: &Budget, colour: &str) -> usize {
+    budget.get(colour).copied().unwrap_or(0)
+}
+
+pub fn possible(game: &Game, budget: &Budget) -> bool {
     game.rounds.iter().all(|round| {
         round
             .iter()
-            .all(|ball| match budget.get(ball.colour.as_str()) {
-                Some(&b) => b >= ball.count,
-                None => false,
-            })
+            .all(|ball| allowance(budget, &ball.colour) >= ball.count)
     })
 }
 
+fn budget() -> Budget {
+    [("red", 12), ("green", 13), ("blue", 14)]
+        .into_iter()
+        .map(|(colour, count)| (colour.to_string(), count))
+        .collect()
+}
+
 #[aoc(day2, part1)]
 pub fn solve_part1(input: &Vec<Game>) -> usize {
-    let budget = HashMap::from([("red", 12), ("green", 13), ("blue", 14)]);
-    input
-        .iter()
-        .filter(|&g| possible(g, &budget))
-        .map(|g| g.nr)
-        .sum()
+    let budget = budget();
+    let nrs = input.iter().filter(|&g| possible(g, &budget)).map(|g| g.nr);
+    checked::sum(2, "sum of game numbers", nrs)
 }
 
-fn power(game: &Game) -> usize {
-    let mut req = HashMap::new();
+pub fn requirement(game: &Game) -> Budget {
+    let mut req = Budget::new();
     for r in game.rounds.iter() {
         for ball in r.iter() {
-            let entry = req.entry(&ball.colour).or_insert(ball.count);
+            let entry = req.entry(ball.colour.clone()).or_insert(ball.count);
             *entry = max(*entry, ball.count);
         }
     }
-    req.values().product()
+    req
+}
+
+fn power(game: &Game) -> usize {
+    checked::product(2, "power", requirement(game).values().copied())
 }
 
 #[aoc(day2, part2)]
 pub fn solve_part2(input: &Vec<Game>) -> usize {
-    input.iter().map(power).sum()
+    checked::sum(2, "sum of powers", input.iter().map(power))
+}
+
+pub fn colours(games: &[Game]) -> Vec<String> {
+    let colours: BTreeSet<&String> = games
+        .iter()
+        .flat_map(|g| g.rounds.iter().flatten().map(|ball| &ball.colour))
+        .collect();
+    colours.into_iter().cloned().collect()
+}
+
+pub fn min_budget_for(games: &[Game], chosen: &[usize]) -> Budget {
+    let mut budget = Budget::new();
+    for game in games.iter().filter(|g| chosen.contains(&g.nr)) {
+        for (colour, count) in requirement(game) {
+            let entry = budget.entry(colour).or_insert(count);
+            *entry = max(*entry, count);
+        }
+    }
+    budget
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub game: usize,
+    pub round: usize,
+    pub colour: String,
+    pub count: usize,
+    pub excess: usize,
+}
+
+pub fn violations(games: &[Game], budget: &Budget) -> Vec<Violation> {
+    let mut result = Vec::new();
+    for game in games {
+        for (round, balls) in game.rounds.iter().enumerate() {
+            for ball in balls {
+                let allowed = allowance(budget, &ball.colour);
+                if ball.count > allowed {
+                    result.push(Violation {
+                        game: game.nr,
+                        round,
+                        colour: ball.colour.clone(),
+                        count: ball.count,
+                        excess: ball.count - allowed,
+                    });
+                }
+            }
+        }
+    }
+    result
+}
+
+// The minimal budgets (no colour can be lowered) that make at least k games possible.
+// Such budgets only ever use values some game requires, so only those are searched.
+pub fn pareto_frontier(games: &[Game], k: usize) -> Vec<Budget> {
+    let colours = colours(games);
+    let reqs: Vec<Vec<usize>> = games
+        .iter()
+        .map(|g| {
+            let req = requirement(g);
+            colours.iter().map(|c| allowance(&req, c)).collect()
+        })
+        .collect();
+    let candidates: Vec<Vec<usize>> = (0..colours.len())
+        .map(|c| {
+            let values: BTreeSet<usize> = reqs.iter().map(|r| r[c]).chain([0]).collect();
+            values.into_iter().collect()
+        })
+        .collect();
+
+    let mut feasible: Vec<Vec<usize>> = Vec::new();
+    let mut partial = Vec::with_capacity(colours.len());
+    let all: Vec<usize> = (0..reqs.len()).collect();
+    search(&reqs, &candidates, k, &all, &mut partial, &mut feasible);
+
+    feasible.sort_by_key(|b| b.iter().sum::<usize>());
+    let mut frontier: Vec<Vec<usize>> = Vec::new();
+    for b in feasible {
+        if !frontier
+            .iter()
+            .any(|f| f.iter().zip(&b).all(|(x, y)| x <= y))
+        {
+            frontier.push(b);
+        }
+    }
+    frontier
+        .into_iter()
+        .map(|b| colours.iter().cloned().zip(b).collect())
+        .collect()
+}
+
+fn search(
+    reqs: &[Vec<usize>],
+    candidates: &[Vec<usize>],
+    k: usize,
+    fitting: &[usize],
+    partial: &mut Vec<usize>,
+    feasible: &mut Vec<Vec<usize>>,
+) {
+    if fitting.len() < k {
+        return;
+    }
+    let c = partial.len();
+    if c == candidates.len() {
+        feasible.push(partial.clone());
+        return;
+    }
+    for &value in candidates[c].iter() {
+        let still: Vec<usize> = fitting
+            .iter()
+            .copied()
+            .filter(|&g| reqs[g][c] <= value)
+            .collect();
+        partial.push(value);
+        search(reqs, candidates, k, &still, partial, feasible);
+        partial.pop();
+    }
+}
+
+// Smallest in total number of cubes, ties broken by colour order
+pub fn min_budget_for_k(games: &[Game], k: usize) -> Option<Budget> {
+    pareto_frontier(games, k).into_iter().min_by_key(|b| {
+        (
+            b.values().sum::<usize>(),
+            b.values().copied().collect::<Vec<_>>(),
+        )
+    })
+}
+
+// Bag inference treats every ball shown in a game as an independent draw, with
+// replacement, from a bag of unknown contents. Only the proportions are identifiable
+// this way, so bags are compared by the likelihood of the observed colour counts.
+// A colour shown zero times carries no draws and is ignored.
+pub fn draws(game: &Game) -> Budget {
+    let mut counts = Budget::new();
+    for ball in game.rounds.iter().flatten().filter(|ball| ball.count > 0) {
+        *counts.entry(ball.colour.clone()).or_insert(0) += ball.count;
+    }
+    counts
+}
+
+fn observed(game: &Game) -> (Budget, f64) {
+    let counts = draws(game);
+    let total = counts.values().sum::<usize>() as f64;
+    (counts, total)
+}
+
+pub fn proportions(game: &Game) -> Vec<(String, f64)> {
+    let (counts, total) = observed(game);
+    counts
+        .into_iter()
+        .map(|(colour, k)| (colour, k as f64 / total))
+        .collect()
+}
+
+// Most likely bag holding exactly `size` balls. Each extra ball of colour c gains
+// k_c * ln((n_c + 1) / n_c), which only shrinks as n_c grows, so handing out balls
+// greedily is optimal. Needs room for at least one ball of every observed colour.
+pub fn mle_bag(game: &Game, size: usize) -> Option<Budget> {
+    let counts = draws(game);
+    if counts.len() > size {
+        return None;
+    }
+    let mut bag: Budget = counts.keys().map(|c| (c.clone(), 1)).collect();
+    let gain = |k: usize, n: usize| k as f64 * ((n + 1) as f64 / n as f64).ln();
+    let mut heap: BinaryHeap<(Gain, Reverse<&String>)> = counts
+        .iter()
+        .map(|(c, &k)| (Gain(gain(k, 1)), Reverse(c)))
+        .collect();
+    for _ in counts.len()..size {
+        let (_, Reverse(colour)) = heap.pop()?;
+        let n = bag.get_mut(colour).unwrap();
+        *n += 1;
+        heap.push((Gain(gain(counts[colour], *n)), Reverse(colour)));
+    }
+    Some(bag)
+}
+
+#[derive(PartialEq)]
+struct Gain(f64);
+
+impl Eq for Gain {}
+
+impl PartialOrd for Gain {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Gain {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Up to the multinomial coefficients of the rounds, which do not depend on the bag
+pub fn log_likelihood(game: &Game, bag: &Budget) -> f64 {
+    let size = bag.values().sum::<usize>() as f64;
+    draws(game)
+        .iter()
+        .map(|(colour, &k)| match allowance(bag, colour) {
+            0 => f64::NEG_INFINITY,
+            n => k as f64 * (n as f64 / size).ln(),
+        })
+        .sum()
+}
+
+// ln(L(bag) / L(best proportions)); zero for a perfect fit, -inf if the bag
+// lacks a colour that was drawn
+pub fn log_likelihood_ratio(game: &Game, bag: &Budget) -> f64 {
+    let (counts, total) = observed(game);
+    let best: f64 = counts
+        .values()
+        .map(|&k| k as f64 * (k as f64 / total).ln())
+        .sum();
+    log_likelihood(game, bag) - best
+}
+
+// The G-statistic, -2 ln(likelihood ratio)
+pub fn surprise(game: &Game, bag: &Budget) -> f64 {
+    -2.0 * log_likelihood_ratio(game, bag)
+}
+
+// Game numbers with their surprise, most surprising first
+pub fn rank_by_surprise(games: &[Game], bag: &Budget) -> Vec<(usize, f64)> {
+    let mut ranked: Vec<(usize, f64)> = games.iter().map(|g| (g.nr, surprise(g, bag))).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+pub struct PossibleGames {
+    budget: Budget,
+    total: usize,
+}
+
+impl Default for PossibleGames {
+    fn default() -> PossibleGames {
+        PossibleGames {
+            budget: budget(),
+            total: 0,
+        }
+    }
+}
+
+impl Accumulator for PossibleGames {
+    type Record = Option<usize>;
+    type Total = usize;
+
+    fn feed(&mut self, line: &str) -> Option<usize> {
+        let game = parse_game(line);
+        let ok = possible(&game, &self.budget);
+        if ok {
+            self.total = checked::add(2, "sum of game numbers", self.total, game.nr);
+        }
+        ok.then_some(game.nr)
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+#[derive(Default)]
+pub struct PowerSum {
+    total: usize,
+}
+
+impl Accumulator for PowerSum {
+    type Record = usize;
+    type Total = usize;
+
+    fn feed(&mut self, line: &str) -> usize {
+        let p = power(&parse_game(line));
+        self.total = checked::add(2, "sum of powers", self.total, p);
+        p
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry::fixture, stream};
+
+    #[test]
+    fn streaming() {
+        let input = fixture(2, "example");
+        let records: Vec<(Option<usize>, usize)> =
+            stream::lines(input.as_bytes(), PossibleGames::default())
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(records[2], (None, 3));
+        assert_eq!(records[4], (Some(5), 8));
+        let powers = stream::lines(input.as_bytes(), PowerSum::default());
+        assert_eq!(powers.finish().unwrap(), 2286);
+    }
+
+    fn budget_of(counts: &[(&str, usize)]) -> Budget {
+        counts.iter().map(|&(c, n)| (c.to_string(), n)).collect()
+    }
+
+    #[test]
+    fn budget_queries() {
+        let input = fixture(2, "example");
+        let games = input_generator(&input);
+        assert_eq!(colours(&games), vec!["blue", "green", "red"]);
+        assert_eq!(
+            min_budget_for(&games, &[1, 2, 5]),
+            budget_of(&[("blue", 6), ("green", 3), ("red", 6)])
+        );
+        let broken: Vec<(usize, usize, String, usize)> = violations(&games, &budget())
+            .into_iter()
+            .map(|v| (v.game, v.round, v.colour, v.excess))
+            .collect();
+        assert_eq!(
+            broken,
+            vec![
+                (3, 0, "red".to_string(), 8),
+                (4, 2, "blue".to_string(), 1),
+                (4, 2, "red".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn frontier() {
+        let input = fixture(2, "example");
+        let games = input_generator(&input);
+        assert_eq!(
+            pareto_frontier(&games, 5),
+            vec![budget_of(&[("blue", 15), ("green", 13), ("red", 20)])]
+        );
+        assert_eq!(
+            pareto_frontier(&games, 4),
+            vec![
+                budget_of(&[("blue", 15), ("green", 3), ("red", 14)]),
+                budget_of(&[("blue", 6), ("green", 13), ("red", 20)]),
+            ]
+        );
+        assert_eq!(
+            min_budget_for_k(&games, 3),
+            Some(budget_of(&[("blue", 6), ("green", 3), ("red", 6)]))
+        );
+        assert_eq!(min_budget_for_k(&games, 6), None);
+        assert_eq!(
+            pareto_frontier(&games, 0),
+            vec![budget_of(&[("blue", 0), ("green", 0), ("red", 0)])]
+        );
+    }
+
+    const BAGS: &str = r#"Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 4 red, 4 green; 4 blue
+Game 3: 20 red, 1 blue"#;
+
+    #[test]
+    fn maximum_likelihood() {
+        let games = input_generator(BAGS);
+        let seen = budget_of(&[("blue", 9), ("green", 4), ("red", 5)]);
+        assert_eq!(draws(&games[0]), seen);
+        assert_eq!(mle_bag(&games[0], 18), Some(seen.clone()));
+        assert_eq!(
+            mle_bag(&games[0], 36),
+            Some(budget_of(&[("blue", 18), ("green", 8), ("red", 10)]))
+        );
+        assert_eq!(
+            mle_bag(&games[1], 6),
+            Some(budget_of(&[("blue", 2), ("green", 2), ("red", 2)]))
+        );
+        assert_eq!(
+            mle_bag(&games[2], 3),
+            Some(budget_of(&[("blue", 1), ("red", 2)]))
+        );
+        assert_eq!(mle_bag(&games[0], 2), None);
+        let p = proportions(&games[0]);
+        assert_eq!(p[0].0, "blue");
+        assert!((p[0].1 - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn likelihood_ratio() {
+        let games = input_generator(BAGS);
+        let seen = budget_of(&[("blue", 9), ("green", 4), ("red", 5)]);
+        assert!(log_likelihood_ratio(&games[0], &seen).abs() < 1e-12);
+        let uneven = budget_of(&[("blue", 1), ("green", 1), ("red", 4)]);
+        assert!(log_likelihood_ratio(&games[0], &uneven) < 0.0);
+        // 4 of each colour from an even bag: ln((1/3)^12 / (1/3)^12) = 0
+        let even = budget_of(&[("blue", 5), ("green", 5), ("red", 5)]);
+        assert!(surprise(&games[1], &even).abs() < 1e-12);
+        assert_eq!(
+            log_likelihood_ratio(&games[2], &budget_of(&[("red", 3)])),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn ranking() {
+        let games = input_generator(BAGS);
+        let even = budget_of(&[("blue", 5), ("green", 5), ("red", 5)]);
+        let order: Vec<usize> = rank_by_surprise(&games, &even)
+            .into_iter()
+            .map(|(nr, _)| nr)
+            .collect();
+        assert_eq!(order, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn zero_counts() {
+        let games = input_generator("Game 1: 0 red, 2 blue; 0 green\nGame 2: 0 red");
+        let seen = budget_of(&[("blue", 2)]);
+        assert_eq!(draws(&games[0]), seen);
+        assert_eq!(mle_bag(&games[0], 2), Some(seen.clone()));
+        let even = budget_of(&[("blue", 1), ("red", 1)]);
+        assert!((log_likelihood(&games[0], &even) - 2.0 * 0.5f64.ln()).abs() < 1e-12);
+        assert!((surprise(&games[0], &even) + 4.0 * 0.5f64.ln()).abs() < 1e-12);
+        assert!(proportions(&games[1]).is_empty());
+        assert_eq!(log_likelihood_ratio(&games[1], &even), 0.0);
+        assert_eq!(surprise(&games[1], &seen), 0.0);
+    }
 }
@@ -2,6 +2,8 @@ use std::collections::{HashMap, VecDeque};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Node {
     Broadcaster,
@@ -28,7 +30,7 @@ pub fn input_generator(input: &str) -> Task {
 }
 
 #[aoc(day20, part1)]
-fn solve_part1(input: &Task) -> u64 {
+pub fn solve_part1(input: &Task) -> u64 {
     let mut fflops = 0u64;
     let mut conj_masks = [0u64; 64];
     let mut conjs = [0u64; 64];
@@ -40,6 +42,8 @@ fn solve_part1(input: &Task) -> u64 {
         .collect();
     names.insert("rx".to_string(), names.len());
     names.insert("output".to_string(), names.len());
+    // Module states are bits of a u64
+    checked::capacity(20, "module count", names.len(), 64);
 
     let mut rev_names: Vec<String> = input.iter().map(|(n, _)| n.clone()).collect();
     rev_names.push("rx".to_string());
@@ -78,9 +82,9 @@ fn solve_part1(input: &Task) -> u64 {
         work.push_back((0, bcast, false));
         while let Some((src, dst, lvl)) = work.pop_front() {
             if lvl {
-                his += 1;
+                his = checked::add(20, "pulse count", his, 1);
             } else {
-                los += 1;
+                los = checked::add(20, "pulse count", los, 1);
             }
             // println!(
             //     "{} -{}-> {}",
@@ -117,11 +121,11 @@ fn solve_part1(input: &Task) -> u64 {
             }
         }
     }
-    his * los
+    checked::mul(20, "pulse product", his, los)
 }
 
 #[aoc(day20, part2)]
-fn solve_part2(input: &Task) -> u64 {
+pub fn solve_part2(input: &Task) -> u64 {
     let mut fflops = 0u64;
     let mut conj_masks = [0u64; 64];
     let mut conjs = [0u64; 64];
@@ -133,6 +137,8 @@ fn solve_part2(input: &Task) -> u64 {
         .collect();
     names.insert("rx".to_string(), names.len());
     names.insert("output".to_string(), names.len());
+    // Module states are bits of a u64
+    checked::capacity(20, "module count", names.len(), 64);
 
     let mut rev_names: Vec<String> = input.iter().map(|(n, _)| n.clone()).collect();
     rev_names.push("rx".to_string());
@@ -200,7 +206,7 @@ fn solve_part2(input: &Task) -> u64 {
                         let name = &rev_names[dst];
                         if terms.contains(name) {
                             // println!("Conj {} activated at {}", name, i + 1);
-                            answer *= i + 1;
+                            answer = checked::mul(20, "cycle product", answer, i + 1);
                             terms.remove(terms.iter().position(|x| x == name).unwrap());
                         }
                     }
@@ -213,36 +219,3 @@ fn solve_part2(input: &Task) -> u64 {
     }
     answer
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-broadcaster -> a, b, c
-%a -> b
-%b -> c
-%c -> inv
-&inv -> a"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 32000000);
-    }
-
-    #[test]
-    fn example2() {
-        let input = r#"
-broadcaster -> a
-%a -> inv, con
-&inv -> b
-%b -> con
-&con -> output"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 11687500);
-    }
-}
@@ -4,6 +4,13 @@ use std::{
 };
 
 use aoc_parse::{parser, prelude::*};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::{
+    checked,
+    poly::{PolyError, Sequence},
+};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Loc {
@@ -26,7 +33,7 @@ pub fn input_generator(input: &str) -> Task {
     p.parse(input).unwrap()
 }
 
-fn part1(input: &Task, n: usize) -> usize {
+pub fn part1(input: &Task, n: usize) -> usize {
     let mut is = usize::MAX;
     let mut js = 0;
     for (i, row) in input.iter().enumerate() {
@@ -78,11 +85,11 @@ fn part1(input: &Task, n: usize) -> usize {
 }
 
 #[aoc(day21, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     part1(input, 64)
 }
 
-fn part2(input: &Task, n: usize) -> u64 {
+fn reachable_counts(input: &Task, n: usize) -> Vec<u64> {
     let mut is = usize::MAX;
     let mut js = 0;
     for (i, row) in input.iter().enumerate() {
@@ -125,9 +132,8 @@ fn part2(input: &Task, n: usize) -> u64 {
     let mut boundary: HashSet<(i32, i32)> = HashSet::from_iter([(is as i32, js as i32)]);
     let mut prev = 0u64;
     let mut count = 1u64;
-    let mut old_count = 0;
-    let mut old_old_count = 0;
-    for i in 0..n {
+    let mut counts = vec![count];
+    for _ in 0..n {
         let mut next = HashSet::new();
         for (ii, jj) in boundary.iter() {
             let ni = normalize(*ii, input.len());
@@ -142,36 +148,30 @@ fn part2(input: &Task, n: usize) -> u64 {
                     .filter(|(i, j)| !seen.contains(&(*i, *j))),
             );
         }
-        let nn = prev + next.len() as u64;
+        let nn = checked::add(21, "reachable plots", prev, next.len() as u64);
         prev = count;
         count = nn;
-
-        // println!(
-        //     "Step {}: {} {}",
-        //     i,
-        //     count,
-        //     count as f64 / (0.001 + (0.84405 * (i * i) as f64))
-        // );
-
-        if i > 0 && (26501365 - i - 1) % input.len() == 0 {
-            let cycles_left = (26501365 - i - 1) / input.len();
-            if i > 5000 && cycles_left % 10 == 0 {
-                println!("Step {}, {} cycles to go", i, cycles_left);
-                let left = cycles_left as u64;
-                let a = count - old_count;
-                let d = (count - old_count) - (old_count - old_old_count);
-                let a = a + d;
-                let predict = count + left * (2 * a + (left - 1) * d) / 2;
-                println!("Speed: {}, Acc: {}, Prognosis: {}", a, d, predict);
-            }
-            old_old_count = old_count;
-            old_count = count;
-        }
+        counts.push(count);
 
         swap(&mut seen, &mut boundary);
         swap(&mut boundary, &mut next);
     }
-    count
+    counts
+}
+
+pub fn part2(input: &Task, n: usize) -> u64 {
+    reachable_counts(input, n)[n]
+}
+
+// Samples every period-th step and extends the trend once a fourth sample confirms it
+fn extrapolated(input: &Task, n: usize) -> Result<u64, PolyError> {
+    let period = input.len();
+    let offset = n % period;
+    let counts = reachable_counts(input, offset + 3 * period);
+    let samples: Vec<BigInt> = (0..4).map(|k| counts[offset + k * period].into()).collect();
+    let trend = Sequence::fit(samples)?;
+    let plots = trend.at(&BigInt::from(n / period))?;
+    plots.to_u64().ok_or(PolyError::Overflow)
 }
 
 fn normalize(x: i32, h: usize) -> usize {
@@ -179,45 +179,24 @@ fn normalize(x: i32, h: usize) -> usize {
 }
 
 #[aoc(day21, part2)]
-fn solve_part2(input: &Task) -> u64 {
-    part2(input, 26501365)
+pub fn solve_part2(input: &Task) -> u64 {
+    match extrapolated(input, 26501365) {
+        Ok(plots) => plots,
+        Err(PolyError::NotPolynomial) => panic!("reachable plots do not grow polynomially"),
+        Err(PolyError::Overflow) => panic!("more plots are reachable than fit in a u64"),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &str = r#"...........
-.....###.#.
-.###.##..#.
-..#.#...#..
-....#.#....
-.##..S####.
-.##..#...#.
-.......##..
-.##.#.####.
-.##..##.##.
-..........."#;
-
-    #[test]
-    fn example1() {
-        let parsed = input_generator(INPUT);
-        let result1 = part1(&parsed, 6);
-        assert_eq!(result1, 16);
-        assert_eq!(part2(&parsed, 6), 16);
-        assert_eq!(part2(&parsed, 10), 50);
-    }
-
-    #[test]
-    fn example2() {
-        let parsed = input_generator(INPUT);
-        assert_eq!(part2(&parsed, 100), 6536);
-        assert_eq!(part2(&parsed, 500), 167004);
-    }
-
     #[test]
-    fn example3() {
-        let parsed = input_generator(INPUT);
-        assert_eq!(part2(&parsed, 1000), 668697);
+    fn open_field() {
+        let mut field = vec![vec![Loc::Plot; 11]; 11];
+        field[5][5] = Loc::Start;
+        assert_eq!(part2(&field, 30), 31 * 31);
+        assert_eq!(extrapolated(&field, 26501365), Ok(26501366 * 26501366));
+        assert_eq!(extrapolated(&field, 1 << 33), Err(PolyError::Overflow));
     }
 }
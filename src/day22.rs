@@ -2,6 +2,8 @@ use std::collections::HashSet;
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct Block {
     xf: usize,
@@ -76,14 +78,14 @@ fn dependencies(input: &Task) -> Vec<HashSet<usize>> {
             .collect();
         bearing.push(support);
         for (x, y) in footprint {
-            work[x][y] = (level + block.height(), i);
+            work[x][y] = (checked::add(22, "stack height", level, block.height()), i);
         }
     }
     bearing
 }
 
 #[aoc(day22, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let bearing: HashSet<usize> = dependencies(input)
         .iter()
         .filter_map(|support| {
@@ -99,7 +101,7 @@ fn solve_part1(input: &Task) -> usize {
 }
 
 #[aoc(day22, part2)]
-fn solve_part2(input: &Task) -> usize {
+pub fn solve_part2(input: &Task) -> usize {
     let deps = dependencies(input);
 
     let mut result = 0;
@@ -111,32 +113,9 @@ fn solve_part2(input: &Task) -> usize {
             }
         }
         if gone.len() > 1 {
-            result += gone.len() - 1;
+            result = checked::add(22, "falling bricks", result, gone.len() - 1);
         }
     }
 
     result
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-1,0,1~1,2,1
-0,0,2~2,0,2
-0,2,3~2,2,3
-0,0,4~0,2,4
-2,0,5~2,2,5
-0,1,6~2,1,6
-1,1,8~1,1,9"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 5);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 7);
-    }
-}
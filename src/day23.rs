@@ -2,6 +2,8 @@ use std::{collections::{HashMap, HashSet, VecDeque}, cmp::max};
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub enum Loc {
     Path,
@@ -40,7 +42,7 @@ pub fn input_generator(input: &str) -> Task {
 }
 
 #[aoc(day23, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let start = input.keys().min().unwrap().clone();
     let end = input.keys().max().unwrap().clone();
 
@@ -105,8 +107,9 @@ fn solve_part1(input: &Task) -> usize {
     while let Some(n) = work.pop_front() {
         for ((f, t), d) in edges.iter() {
             if *f == n {
-                if dist[n] + d > dist[*t] {
-                    dist[*t] = dist[n] + d;
+                let through = checked::add(23, "path length", dist[n], *d);
+                if through > dist[*t] {
+                    dist[*t] = through;
                 }
                 work.push_back(*t);
             }
@@ -116,7 +119,7 @@ fn solve_part1(input: &Task) -> usize {
 }
 
 #[aoc(day23, part2)]
-fn solve_part2(input: &Task) -> usize {
+pub fn solve_part2(input: &Task) -> usize {
     let start = input.keys().min().unwrap().clone();
     let end = input.keys().max().unwrap().clone();
 
@@ -165,6 +168,8 @@ fn solve_part2(input: &Task) -> usize {
         }
     }
 
+    // Visited junctions are bits of a u64
+    checked::capacity(23, "junction count", nodes.len(), 64);
     let mut dist = 0;
     let mut work: VecDeque<(usize, u64, usize)> = VecDeque::new();
     work.push_back((0, 1, 0));
@@ -176,49 +181,11 @@ fn solve_part2(input: &Task) -> usize {
             if *f == n {
                 let step_mask = 1u64 << *t;
                 if step_mask & mask == 0 {
-                    work.push_back((*t, step_mask | mask, d + dd));
+                    let length = checked::add(23, "path length", d, *dd);
+                    work.push_back((*t, step_mask | mask, length));
                 }
             }
         }
     }
     dist
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-#.#####################
-#.......#########...###
-#######.#########.#.###
-###.....#.>.>.###.#.###
-###v#####.#v#.###.#.###
-###.>...#.#.#.....#...#
-###v###.#.#.#########.#
-###...#.#.#.......#...#
-#####.#.#.#######.#.###
-#.....#.#.#.......#...#
-#.#####.#.#.#########v#
-#.#...#...#...###...>.#
-#.#.#v#######v###.###v#
-#...#.>.#...>.>.#.###.#
-#####v#.#.###v#.#.###.#
-#.....#...#...#.#.#...#
-#.#########.###.#.#.###
-#...###...#...#...#.###
-###.###.#.###v#####v###
-#...#...#.#.>.>.#.>.###
-#.###.###.#.###.#.#v###
-#.....###...###...#...#
-#####################.#"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 94);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 154);
-    }
-}
@@ -3,6 +3,8 @@ use std::collections::HashSet;
 use aoc_parse::{parser, prelude::*};
 use rand::Rng;
 
+use crate::checked;
+
 #[derive(Eq, PartialEq, Clone, Hash, Debug)]
 pub struct Node {
     name: String,
@@ -19,7 +21,7 @@ pub fn input_generator(input: &str) -> Task {
 }
 
 #[aoc(day25, part1)]
-fn solve_part1(input: &Task) -> usize {
+pub fn solve_part1(input: &Task) -> usize {
     let mut nodes = HashSet::new();
     for n in input.iter() {
         nodes.insert(n.name.clone());
@@ -50,7 +52,7 @@ fn solve_part1(input: &Task) -> usize {
             let e = rng.gen_range(0..we.len());
             let (u, v) = we.remove(e);
 
-            ws[u] += ws[v];
+            ws[u] = checked::add(25, "component size", ws[u], ws[v]);
             ws[v] = 0;
 
             for edge in we.iter_mut() {
@@ -64,34 +66,7 @@ fn solve_part1(input: &Task) -> usize {
             we.retain(|(u, v)| *u != *v);
         }
         if we.len() == 3 {
-            return ws[we[0].0] * ws[we[0].1];
+            return checked::mul(25, "component sizes", ws[we[0].0], ws[we[0].1]);
         }
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example1() {
-        let input = r#"
-jqt: rhn xhk nvd
-rsh: frs pzl lsr
-xhk: hfx
-cmg: qnr nvd lhk bvb
-rhn: xhk bvb hfx
-bvb: xhk hfx
-pzl: lsr hfx nvd
-qnr: nvd
-ntq: jqt hfx bvb xhk
-nvd: lhk
-lsr: lhk
-rzs: qnr cmg lsr rsh
-frs: qnr lhk lsr"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 54);
-    }
-}
@@ -1,160 +1,342 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    ops::Range,
+};
+
+use crate::checked;
 
-const RADIX: u32 = 10;
 type Coords = (i32, i32);
 
-#[aoc_generator(day3)]
-pub fn input_generator(input: &str) -> (HashMap<Coords, char>, Vec<(u32, Coords)>) {
-    let mut symbols: HashMap<Coords, char> = HashMap::new();
-    let mut numbers: Vec<(u32, Coords)> = Vec::new();
-
-    input.lines().enumerate().for_each(|(i, l)| {
-        let mut acc: u32 = 0;
-        let mut pos: Coords = (0, 0);
-        l.trim().chars().enumerate().for_each(|(j, c)| {
-            if c.is_digit(RADIX) {
-                if acc == 0 {
-                    pos = (i as i32, j as i32);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: u32,
+    pub row: i32,
+    // Columns of the digits as written, leading zeros included
+    pub span: Range<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub kind: char,
+    pub pos: Coords,
+}
+
+// Numbers and symbols, linked both ways wherever a symbol borders a number's span
+pub struct Schematic {
+    pub numbers: Vec<Number>,
+    pub symbols: Vec<Symbol>,
+    symbols_of: Vec<Vec<usize>>,
+    numbers_of: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    pub fn parse(input: &str) -> Schematic {
+        let mut symbols: Vec<Symbol> = Vec::new();
+        let mut numbers: Vec<Number> = Vec::new();
+
+        for (i, l) in input.lines().enumerate() {
+            let row = i as i32;
+            let l = l.trim();
+            let mut start: Option<usize> = None;
+            for (j, c) in l.char_indices().chain([(l.len(), '.')]) {
+                if c.is_ascii_digit() {
+                    start.get_or_insert(j);
+                    continue;
                 }
-                acc = acc * RADIX + c.to_digit(RADIX).unwrap();
-            } else {
-                if acc != 0 {
-                    numbers.push((acc, pos));
+                if let Some(from) = start.take() {
+                    numbers.push(Number {
+                        value: l[from..j].parse().unwrap(),
+                        row,
+                        span: from as i32..j as i32,
+                    });
                 }
-                acc = 0;
                 if c != '.' {
-                    symbols.insert((i as i32, j as i32), c);
+                    symbols.push(Symbol {
+                        kind: c,
+                        pos: (row, j as i32),
+                    });
                 }
             }
-        });
-        if acc != 0 {
-            numbers.push((acc, pos));
         }
-    });
-    (symbols, numbers)
-}
-
-fn neighbours(number: u32, pos: Coords, symbols: &HashMap<Coords, char>) -> Vec<Coords> {
-    let (y, x) = pos;
-    let len = number.to_string().len() as i32;
-    let lt = x - 1;
-    let rt = x + len;
-    let mut nbrs: Vec<Coords> = (lt..=rt)
-        .flat_map(|xn| [(y - 1, xn), (y + 1, xn)])
-        .filter_map(|pos| {
-            if symbols.contains_key(&pos) {
-                Some(pos)
-            } else {
-                None
+
+        let at: HashMap<Coords, usize> = symbols
+            .iter()
+            .enumerate()
+            .map(|(s, symbol)| (symbol.pos, s))
+            .collect();
+        let mut symbols_of = vec![Vec::new(); numbers.len()];
+        let mut numbers_of = vec![Vec::new(); symbols.len()];
+        for (n, number) in numbers.iter().enumerate() {
+            for pos in border(number) {
+                if let Some(&s) = at.get(&pos) {
+                    symbols_of[n].push(s);
+                    numbers_of[s].push(n);
+                }
             }
-        })
-        .collect();
-    if symbols.contains_key(&(y, lt)) {
-        nbrs.push((y, lt));
+        }
+        Schematic {
+            numbers,
+            symbols,
+            symbols_of,
+            numbers_of,
+        }
     }
-    if symbols.contains_key(&(y, rt)) {
-        nbrs.push((y, rt));
+
+    pub fn symbols_of(&self, number: usize) -> impl Iterator<Item = &Symbol> {
+        self.symbols_of[number].iter().map(|&s| &self.symbols[s])
     }
-    nbrs
-}
 
-#[aoc(day3, part1)]
-pub fn solve_part1((symbols, numbers): &(HashMap<Coords, char>, Vec<(u32, Coords)>)) -> u32 {
-    numbers
-        .iter()
-        .filter_map(|(number, pos)| {
-            if neighbours(*number, *pos, symbols).is_empty() {
-                None
-            } else {
-                Some(number)
+    pub fn numbers_of(&self, symbol: usize) -> impl Iterator<Item = &Number> {
+        self.numbers_of[symbol].iter().map(|&n| &self.numbers[n])
+    }
+
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.part_numbers_of(&SymbolClass::Any)
+    }
+
+    // Numbers touching at least one symbol of the class
+    pub fn part_numbers_of<'a>(
+        &'a self,
+        class: &'a SymbolClass,
+    ) -> impl Iterator<Item = &'a Number> {
+        self.numbers
+            .iter()
+            .enumerate()
+            .filter(move |&(n, _)| self.symbols_of(n).any(|s| class.contains(s.kind)))
+            .map(|(_, number)| number)
+    }
+
+    pub fn gears(&self, rule: &GearRule) -> Report {
+        let mut gears = Vec::new();
+        for (s, symbol) in self.symbols.iter().enumerate() {
+            if !rule.class.contains(symbol.kind) || !rule.arity.admits(self.numbers_of[s].len()) {
+                continue;
             }
-        })
-        .sum()
+            let values: Vec<u64> = self.numbers_of(s).map(|n| n.value as u64).collect();
+            gears.push((symbol.pos, rule.value.apply(&values)));
+        }
+        let total = checked::sum(3, "sum of gears", gears.iter().map(|&(_, v)| v));
+        Report { gears, total }
+    }
+
+    pub fn reports(&self, rules: &[GearRule]) -> Vec<Report> {
+        rules.iter().map(|rule| self.gears(rule)).collect()
+    }
+
+    // Each number once, however many symbols of that kind it touches
+    pub fn numbers_adjacent_to(&self, kind: char) -> Vec<&Number> {
+        let chosen: BTreeSet<usize> = self
+            .symbols
+            .iter()
+            .zip(&self.numbers_of)
+            .filter(|(symbol, _)| symbol.kind == kind)
+            .flat_map(|(_, numbers)| numbers.iter().copied())
+            .collect();
+        chosen.into_iter().map(|n| &self.numbers[n]).collect()
+    }
+
+    pub fn symbols_touching(&self, n: usize) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .zip(&self.numbers_of)
+            .filter(|(_, numbers)| numbers.len() == n)
+            .map(|(symbol, _)| symbol)
+            .collect()
+    }
 }
 
-#[aoc(day3, part2)]
-pub fn solve_part2((symbols, numbers): &(HashMap<Coords, char>, Vec<(u32, Coords)>)) -> u64 {
-    let stars: HashMap<Coords, char> = symbols
-        .iter()
-        .filter_map(|(&key, &v)| if v == '*' { Some((key, '*')) } else { None })
-        .collect();
-
-    let mut gears: HashMap<Coords, Vec<u32>> = HashMap::new();
-
-    for &(number, pos) in numbers.iter() {
-        let nbrs = neighbours(number, pos, &stars);
-        for &p in nbrs.iter() {
-            gears.entry(p).or_insert_with(Vec::new).push(number);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolClass {
+    Any,
+    OneOf(String),
+    NoneOf(String),
+}
+
+impl SymbolClass {
+    pub fn contains(&self, kind: char) -> bool {
+        match self {
+            SymbolClass::Any => true,
+            SymbolClass::OneOf(kinds) => kinds.contains(kind),
+            SymbolClass::NoneOf(kinds) => !kinds.contains(kind),
         }
     }
-    let good_gears: Vec<(u64, u64)> = gears
-        .values()
-        .filter_map(|nrs| {
-            if nrs.len() == 2 {
-                Some((nrs[0] as u64, nrs[1] as u64))
-            } else {
-                None
-            }
-        })
-        .collect();
-    good_gears.iter().map(|(a, b)| a * b).sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    fn admits(&self, n: usize) -> bool {
+        match *self {
+            Arity::Exactly(k) => n == k,
+            Arity::AtLeast(k) => n >= k,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum GearValue {
+    Sum,
+    Product,
+    Custom(fn(&[u64]) -> u64),
+}
+
+impl GearValue {
+    fn apply(&self, values: &[u64]) -> u64 {
+        match self {
+            GearValue::Sum => checked::sum(3, "gear value", values.iter().copied()),
+            GearValue::Product => checked::product(3, "gear value", values.iter().copied()),
+            GearValue::Custom(f) => f(values),
+        }
+    }
+}
+
+pub struct GearRule {
+    pub class: SymbolClass,
+    pub arity: Arity,
+    pub value: GearValue,
+}
+
+impl GearRule {
+    // The puzzle's own rule: a `*` next to exactly two numbers is worth their product
+    pub fn standard() -> GearRule {
+        GearRule {
+            class: SymbolClass::OneOf("*".to_string()),
+            arity: Arity::Exactly(2),
+            value: GearValue::Product,
+        }
+    }
+}
+
+// Every symbol the rule matched, by position, with its value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub gears: Vec<(Coords, u64)>,
+    pub total: u64,
+}
+
+fn border(number: &Number) -> impl Iterator<Item = Coords> + '_ {
+    let y = number.row;
+    let lt = number.span.start - 1;
+    let rt = number.span.end;
+    (lt..=rt)
+        .flat_map(move |x| [(y - 1, x), (y + 1, x)])
+        .chain([(y, lt), (y, rt)])
+}
+
+#[aoc_generator(day3)]
+pub fn input_generator(input: &str) -> Schematic {
+    Schematic::parse(input)
+}
+
+#[aoc(day3, part1)]
+pub fn solve_part1(schematic: &Schematic) -> u32 {
+    checked::sum(
+        3,
+        "sum of part numbers",
+        schematic.part_numbers().map(|n| n.value),
+    )
+}
+
+#[aoc(day3, part2)]
+pub fn solve_part2(schematic: &Schematic) -> u64 {
+    schematic.gears(&GearRule::standard()).total
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::fixture;
 
     #[test]
-    fn part2_example() {
+    fn zeros() {
         let example = r#"
-467..114..
-...*......
-..35..633.
-......#...
-617*......
-.....+.58.
-..592.....
-......755.
-...$.*....
-.664.598.."#;
-        let input = input_generator(example);
-        let result = solve_part2(&input);
-        assert_eq!(result, 467835);
-    }
-
-    // https://www.reddit.com/r/adventofcode/comments/189q9wv/2023_day_3_another_sample_grid_to_use/
+*.....
+.007..
+...0#."#;
+        let schematic = input_generator(example);
+        assert_eq!(
+            schematic.numbers,
+            vec![
+                Number {
+                    value: 7,
+                    row: 2,
+                    span: 1..4
+                },
+                Number {
+                    value: 0,
+                    row: 3,
+                    span: 3..4
+                },
+            ]
+        );
+        assert_eq!(solve_part1(&schematic), 7);
+        assert_eq!(solve_part2(&schematic), 0);
+        assert_eq!(
+            schematic.numbers_adjacent_to('*'),
+            vec![&schematic.numbers[0]]
+        );
+        let touching: Vec<char> = schematic
+            .symbols_touching(2)
+            .iter()
+            .map(|s| s.kind)
+            .collect();
+        assert_eq!(touching, vec!['#']);
+        assert_eq!(schematic.symbols_of(1).count(), 1);
+    }
+
     #[test]
-    fn reddit() {
-        let example = r#"
-12.......*..
-+.........34
-.......-12..
-..78........
-..*....60...
-78..........
-.......23...
-....90*12...
-............
-2.2......12.
-.*.........*
-1.1.......56"#;
-        let input = input_generator(example);
-        let result1 = solve_part1(&input);
-        assert_eq!(result1, 413);
-        let result2 = solve_part2(&input);
-        assert_eq!(result2, 6756);
-    }
-
-    // https://www.reddit.com/r/adventofcode/comments/189q9wv/comment/kbsrno0/
+    fn adjacency() {
+        let schematic = input_generator(&fixture(3, "example"));
+        let values =
+            |numbers: Vec<&Number>| -> Vec<u32> { numbers.iter().map(|n| n.value).collect() };
+        assert_eq!(
+            values(schematic.numbers_adjacent_to('*')),
+            vec![467, 35, 617, 755, 598]
+        );
+        assert_eq!(values(schematic.numbers_adjacent_to('+')), vec![592]);
+        assert_eq!(schematic.symbols_touching(1).len(), 4);
+        assert_eq!(schematic.symbols_touching(2).len(), 2);
+        assert!(schematic.symbols_touching(3).is_empty());
+    }
+
     #[test]
-    fn reddit2() {
-        let example = r#"
-.......5......
-..7*..*.......
-...*13*.......
-.......15....."#;
-        let input = input_generator(example);
-        let result2 = solve_part2(&input);
-        assert_eq!(result2, 442);
+    fn gear_rules() {
+        let schematic = input_generator(&fixture(3, "example"));
+        let rules = [
+            GearRule::standard(),
+            GearRule {
+                class: SymbolClass::Any,
+                arity: Arity::AtLeast(2),
+                value: GearValue::Sum,
+            },
+            GearRule {
+                class: SymbolClass::OneOf("#$".to_string()),
+                arity: Arity::Exactly(1),
+                value: GearValue::Custom(|v| v[0] % 100),
+            },
+        ];
+        let reports = schematic.reports(&rules);
+        assert_eq!(reports[0].total, 467835);
+        assert_eq!(
+            reports[1],
+            Report {
+                gears: vec![((1, 3), 467 + 35), ((8, 5), 755 + 598)],
+                total: 467 + 35 + 755 + 598,
+            }
+        );
+        assert_eq!(reports[2].gears, vec![((3, 6), 33), ((8, 3), 64)]);
+
+        let values = |class: SymbolClass| -> u32 {
+            schematic.part_numbers_of(&class).map(|n| n.value).sum()
+        };
+        assert_eq!(values(SymbolClass::Any), 4361);
+        assert_eq!(
+            values(SymbolClass::NoneOf("*".to_string())),
+            633 + 592 + 664
+        );
     }
 }
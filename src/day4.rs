@@ -1,68 +1,451 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet, VecDeque},
+    error::Error,
+    fmt,
+    mem::take,
+};
+
 use aoc_parse::{parser, prelude::*};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::{checked, stream::Accumulator};
+
+// A bitset is used while it costs at most this many bits per winning number
+const BITS_PER_NUMBER: u64 = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Membership {
+    Bits { min: u64, words: Vec<u64> },
+    Hashed(HashSet<u64>),
+}
+
+impl Membership {
+    pub fn new(numbers: &[u64]) -> Membership {
+        if numbers.is_empty() {
+            return Membership::Bits {
+                min: 0,
+                words: Vec::new(),
+            };
+        }
+        let (min, max) = numbers
+            .iter()
+            .fold((u64::MAX, 0), |(lo, hi), &nr| (lo.min(nr), hi.max(nr)));
+        let span = max - min;
+        if span >= BITS_PER_NUMBER.saturating_mul(numbers.len() as u64) {
+            return Membership::Hashed(numbers.iter().copied().collect());
+        }
+        let mut words = vec![0u64; (span / 64 + 1) as usize];
+        for &nr in numbers {
+            let bit = nr - min;
+            words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        Membership::Bits { min, words }
+    }
+
+    pub fn contains(&self, nr: u64) -> bool {
+        match self {
+            Membership::Bits { min, words } => bit(words, nr.wrapping_sub(*min)) == 1,
+            Membership::Hashed(set) => set.contains(&nr),
+        }
+    }
+
+    // Matching once per card, rather than per number, keeps the bitset loop branch-free
+    pub fn count(&self, numbers: &[u64]) -> usize {
+        match self {
+            Membership::Bits { min, words } => numbers
+                .iter()
+                .map(|&nr| bit(words, nr.wrapping_sub(*min)) as usize)
+                .sum(),
+            Membership::Hashed(set) => numbers.iter().filter(|nr| set.contains(nr)).count(),
+        }
+    }
+}
+
+// Numbers below the minimum wrap round to huge offsets and land past the end
+fn bit(words: &[u64], offset: u64) -> u64 {
+    words
+        .get((offset / 64) as usize)
+        .map_or(0, |w| w >> (offset % 64) & 1)
+}
 
 pub struct Card {
-    won: Vec<u32>,
-    got: Vec<u32>,
+    won: Membership,
+    got: Vec<u64>,
 }
 
 impl Card {
-    fn score(&self) -> usize {
-        self.got.iter().filter(|&nr| self.won.contains(nr)).count()
+    pub fn new(won: &[u64], got: Vec<u64>) -> Card {
+        Card {
+            won: Membership::new(won),
+            got,
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        self.won.count(&self.got)
     }
 }
 
+fn parse_card(line: &str) -> Card {
+    let p = parser!(
+        "Card" " "+ u64 ":" " "+ won:repeat_sep(u64, " "+) " |" " "+ got:repeat_sep(u64, " "+) => Card::new(&won, got)
+    );
+    p.parse(line).unwrap()
+}
+
 #[aoc_generator(day4)]
 pub fn input_generator(input: &str) -> Vec<Card> {
-    let p = parser!(lines(
-        "Card" " "+ u32 ":" " "+ won:repeat_sep(u32, " "+) " |" " "+ got:repeat_sep(u32, " "+) => Card { won, got }
-    ));
-    p.parse(input).unwrap()
+    input.lines().map(parse_card).collect()
+}
+
+// Doubles per match, so decks with hundreds of numbers a side soon pass any fixed width
+fn points(card: &Card) -> BigUint {
+    match card.score() {
+        0 => BigUint::zero(),
+        count => BigUint::one() << (count - 1),
+    }
 }
 
 #[aoc(day4, part1)]
-pub fn solve_part1(input: &Vec<Card>) -> usize {
-    input
-        .iter()
-        .map(|card| {
-            let count = card.score();
-            if count == 0 {
-                0
-            } else {
-                1 << (count - 1)
-            }
-        })
-        .sum()
+pub fn solve_part1(input: &Vec<Card>) -> BigUint {
+    checked::sum(4, "points", input.iter().map(points))
 }
 
 #[aoc(day4, part2)]
 pub fn solve_part2(input: &Vec<Card>) -> usize {
-    let mut dp: Vec<usize> = vec![1; input.len()];
-    for (i, card) in input.iter().enumerate() {
-        for j in 1..=card.score() {
-            dp[i + j] += dp[i];
+    Cascade::new(CopyRule::NextN, OverflowPolicy::Clamp)
+        .run(input)
+        .unwrap()
+        .total()
+}
+
+// What happens to a copy won for a card past the end of the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // The copy is discarded
+    Clamp,
+    // The copy goes to the card that far round from the start
+    Wrap,
+    Error,
+}
+
+// Which cards, as offsets from the winning card, receive a copy for a given score
+#[derive(Debug, Clone, Copy)]
+pub enum CopyRule {
+    NextN,
+    EveryKth(usize),
+    Custom(fn(usize) -> Vec<usize>),
+}
+
+impl CopyRule {
+    fn offsets(&self, score: usize) -> Vec<usize> {
+        match *self {
+            CopyRule::NextN => (1..=score).collect(),
+            CopyRule::EveryKth(k) => (1..=score).map(|j| j * k).collect(),
+            CopyRule::Custom(f) => f(score),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CascadeError {
+    PastEnd { card: usize, target: usize },
+    // The card wins copies of itself, directly or through others
+    Unbounded { card: usize },
+}
+
+impl fmt::Display for CascadeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CascadeError::PastEnd { card, target } => {
+                write!(
+                    f,
+                    "card {} wins a copy of card {}, past the end",
+                    card, target
+                )
+            }
+            CascadeError::Unbounded { card } => write!(f, "card {} is won endlessly", card),
         }
     }
-    dp.iter().sum()
+}
+
+impl Error for CascadeError {}
+
+pub struct Cascade {
+    rule: CopyRule,
+    overflow: OverflowPolicy,
+}
+
+// Cards are identified by their index in the deck
+pub struct Tally {
+    pub copies: Vec<usize>,
+    produced: Vec<usize>,
+    sources: Vec<Vec<(usize, usize)>>,
+}
+
+impl Cascade {
+    pub fn new(rule: CopyRule, overflow: OverflowPolicy) -> Cascade {
+        Cascade { rule, overflow }
+    }
+
+    fn targets(&self, card: usize, score: usize, n: usize) -> Result<Vec<usize>, CascadeError> {
+        let mut targets = Vec::new();
+        for offset in self.rule.offsets(score) {
+            let target = card + offset;
+            match self.overflow {
+                _ if target < n => targets.push(target),
+                OverflowPolicy::Clamp => {}
+                OverflowPolicy::Wrap => targets.push(target % n),
+                OverflowPolicy::Error => return Err(CascadeError::PastEnd { card, target }),
+            }
+        }
+        Ok(targets)
+    }
+
+    // Copies flow along the win edges in topological order; a cycle would never settle
+    pub fn run(&self, cards: &[Card]) -> Result<Tally, CascadeError> {
+        let n = cards.len();
+        let targets = cards
+            .iter()
+            .enumerate()
+            .map(|(i, card)| self.targets(i, card.score(), n))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut indegree = vec![0; n];
+        for &t in targets.iter().flatten() {
+            indegree[t] += 1;
+        }
+
+        let mut copies = vec![1; n];
+        let mut produced = vec![0; n];
+        let mut sources = vec![Vec::new(); n];
+        let mut ready: BinaryHeap<Reverse<usize>> =
+            (0..n).filter(|&i| indegree[i] == 0).map(Reverse).collect();
+        let mut done = 0;
+        while let Some(Reverse(i)) = ready.pop() {
+            done += 1;
+            for &t in targets[i].iter() {
+                copies[t] = checked::add(4, "card copies", copies[t], copies[i]);
+                produced[i] = checked::add(4, "card copies", produced[i], copies[i]);
+                sources[t].push((i, copies[i]));
+                indegree[t] -= 1;
+                if indegree[t] == 0 {
+                    ready.push(Reverse(t));
+                }
+            }
+        }
+        if done < n {
+            let card = (0..n).find(|&i| indegree[i] > 0).unwrap();
+            return Err(CascadeError::Unbounded { card });
+        }
+        Ok(Tally {
+            copies,
+            produced,
+            sources,
+        })
+    }
+}
+
+impl Tally {
+    pub fn total(&self) -> usize {
+        checked::sum(4, "total cards", self.copies.iter().copied())
+    }
+
+    // The card that handed out the most copies, and how many; the earliest on a tie
+    pub fn most_productive(&self) -> Option<(usize, usize)> {
+        self.produced
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|&(i, p)| (Reverse(p), i))
+    }
+
+    // The cards that won copies of this one, with how many each; the original adds one more
+    pub fn breakdown(&self, card: usize) -> &[(usize, usize)] {
+        &self.sources[card]
+    }
+}
+
+#[derive(Default)]
+pub struct Points {
+    total: BigUint,
+}
+
+impl Accumulator for Points {
+    type Record = BigUint;
+    type Total = BigUint;
+
+    fn feed(&mut self, line: &str) -> BigUint {
+        let p = points(&parse_card(line));
+        self.total = checked::add(4, "points", take(&mut self.total), p.clone());
+        p
+    }
+
+    fn total(&self) -> BigUint {
+        self.total.clone()
+    }
+}
+
+// Only the copies won for the next few cards are kept, so memory is bounded by the best score
+#[derive(Default)]
+pub struct Copies {
+    pending: VecDeque<usize>,
+    total: usize,
+}
+
+impl Accumulator for Copies {
+    type Record = usize;
+    type Total = usize;
+
+    fn feed(&mut self, line: &str) -> usize {
+        let copies = 1 + self.pending.pop_front().unwrap_or(0);
+        let score = parse_card(line).score();
+        if self.pending.len() < score {
+            self.pending.resize(score, 0);
+        }
+        for pending in self.pending.iter_mut().take(score) {
+            *pending = checked::add(4, "card copies", *pending, copies);
+        }
+        self.total = checked::add(4, "total cards", self.total, copies);
+        copies
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{registry::fixture, stream};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::{hint::black_box, time::Instant};
+
+    #[test]
+    fn streaming() {
+        let input = fixture(4, "example");
+        let copies: Vec<usize> = stream::lines(input.as_bytes(), Copies::default())
+            .map(|r| r.unwrap().0)
+            .collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(
+            stream::lines(input.as_bytes(), Points::default())
+                .finish()
+                .unwrap(),
+            BigUint::from(13u32)
+        );
+    }
+
+    #[test]
+    fn many_matches() {
+        let numbers: Vec<String> = (1..=100).map(|n| n.to_string()).collect();
+        let numbers = numbers.join(" ");
+        let line = format!("Card 1: {} | {}", numbers, numbers);
+        let points = BigUint::one() << 99;
+        assert_eq!(solve_part1(&input_generator(&line)), points);
+        let twice = format!("{}\n{}", line, line);
+        assert_eq!(
+            stream::lines(twice.as_bytes(), Points::default())
+                .finish()
+                .unwrap(),
+            points * 2u32
+        );
+    }
+
+    #[test]
+    fn cascade() {
+        let deck = input_generator(&fixture(4, "example"));
+        let tally = Cascade::new(CopyRule::NextN, OverflowPolicy::Clamp)
+            .run(&deck)
+            .unwrap();
+        assert_eq!(tally.copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(tally.most_productive(), Some((2, 8)));
+        assert_eq!(tally.breakdown(4), [(0, 1), (2, 4), (3, 8)]);
+
+        let every_other = Cascade::new(CopyRule::EveryKth(2), OverflowPolicy::Clamp)
+            .run(&deck)
+            .unwrap();
+        assert_eq!(every_other.copies, vec![1, 1, 2, 2, 4, 4]);
+        let single = Cascade::new(
+            CopyRule::Custom(|score| if score > 0 { vec![1] } else { vec![] }),
+            OverflowPolicy::Error,
+        );
+        assert_eq!(single.run(&deck).unwrap().total(), 16);
+    }
+
+    #[test]
+    fn overflow() {
+        let deck = input_generator("Card 1: 1 | 2\nCard 2: 1 | 2\nCard 3: 1 2 | 1 2");
+        let run = |overflow| Cascade::new(CopyRule::NextN, overflow).run(&deck);
+        assert_eq!(run(OverflowPolicy::Clamp).unwrap().copies, vec![1, 1, 1]);
+        assert_eq!(run(OverflowPolicy::Wrap).unwrap().copies, vec![2, 2, 1]);
+        assert_eq!(
+            run(OverflowPolicy::Error).err(),
+            Some(CascadeError::PastEnd { card: 2, target: 3 })
+        );
+
+        let looping = input_generator("Card 1: 1 | 1\nCard 2: 1 | 1");
+        let result = Cascade::new(CopyRule::NextN, OverflowPolicy::Wrap).run(&looping);
+        assert_eq!(result.err(), Some(CascadeError::Unbounded { card: 0 }));
+    }
+
+    #[test]
+    fn membership() {
+        assert!(matches!(
+            Membership::new(&[3, 70, 5]),
+            Membership::Bits { .. }
+        ));
+        assert!(matches!(
+            Membership::new(&[3, u64::MAX]),
+            Membership::Hashed(_)
+        ));
+        let card = parse_card("Card 1: 18446744073709551615 7 | 7 18446744073709551615 8");
+        assert_eq!(card.score(), 2);
+        let bits = Membership::new(&[64, 127, 128]);
+        let present: Vec<u64> = (0..200).filter(|&nr| bits.contains(nr)).collect();
+        assert_eq!(present, vec![64, 127, 128]);
+        assert!(!Membership::new(&[]).contains(0));
+    }
+
+    fn synthetic_deck(
+        seed: u64,
+        cards: usize,
+        size: usize,
+        spread: u64,
+    ) -> Vec<(Vec<u64>, Vec<u64>)> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut numbers = || -> Vec<u64> { (0..size).map(|_| rng.gen_range(0..spread)).collect() };
+        (0..cards).map(|_| (numbers(), numbers())).collect()
+    }
+
+    #[test]
+    fn synthetic_scores() {
+        for spread in [500, 1 << 40] {
+            for (won, got) in synthetic_deck(38, 200, 300, spread) {
+                let expected = got.iter().filter(|nr| won.contains(nr)).count();
+                assert_eq!(Card::new(&won, got).score(), expected);
+            }
+        }
+    }
 
+    // Scoring a deck with hundreds of numbers a side. Timings only mean something in
+    // an optimised build: cargo test --release -- --ignored throughput
     #[test]
-    fn example() {
-        let input = r#"
-Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
-Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
-Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
-Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
-Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11"#
-            .trim();
-        let parsed = input_generator(input);
-        let answer = solve_part1(&parsed);
-        assert_eq!(answer, 13);
-        let answer2 = solve_part2(&parsed);
-        assert_eq!(answer2, 30);
+    #[ignore]
+    fn throughput() {
+        const ROUNDS: usize = 50;
+        let cards: Vec<Card> = synthetic_deck(38, 20_000, 200, 1000)
+            .into_iter()
+            .map(|(won, got)| Card::new(&won, got))
+            .collect();
+        let start = Instant::now();
+        let matched: usize = (0..ROUNDS)
+            .map(|_| black_box(&cards).iter().map(Card::score).sum::<usize>())
+            .sum();
+        let rate = (ROUNDS * cards.len()) as f64 / start.elapsed().as_secs_f64();
+        assert!(matched > 0);
+        assert!(rate > 1e6, "only {:.0} cards per second", rate);
     }
 }
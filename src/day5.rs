@@ -1,12 +1,13 @@
 use std::{
     cmp::{max, min},
-    collections::VecDeque,
-    iter,
+    fmt::{self, Write},
     ops::Range,
 };
 
 use aoc_parse::{parser, prelude::*};
 
+use crate::checked;
+
 pub struct Map {
     dst: u64,
     src: Range<u64>,
@@ -14,33 +15,18 @@ pub struct Map {
 
 impl Map {
     fn translate(&self, x: &u64) -> u64 {
-        x + self.dst - self.src.start
+        checked::add(5, "translate", *x - self.src.start, self.dst)
     }
 
-    fn translate_range<C>(&self, range: Range<u64>, trimmings: &mut C) -> Option<Range<u64>>
-    where
-        C: Extend<Range<u64>>,
-    {
-        let start = max(self.src.start, range.start);
-        let end = min(self.src.end, range.end);
-
-        if start < end {
-            if range.start < start {
-                trimmings.extend(iter::once(range.start..start));
-            }
-            if range.end > end {
-                trimmings.extend(iter::once(end..range.end));
-            }
-            Some(self.translate(&start)..self.translate(&end))
-        } else {
-            trimmings.extend(iter::once(range));
-            None
-        }
+    fn shift(&self) -> i128 {
+        self.dst as i128 - self.src.start as i128
     }
 }
 
 pub struct Task {
     seeds: Vec<u64>,
+    // Section headers without the trailing " map:", e.g. "seed-to-soil"
+    names: Vec<String>,
     maps: Vec<Vec<Map>>,
 }
 
@@ -53,17 +39,22 @@ pub fn input_generator(input: &str) -> Task {
     let p = parser!(
         seeds:line("seeds: " repeat_sep(u64, " "+))
         line("")
-        maps:sections(
-            line(string(any_char+))
+        sections:sections(
+            name:line(string(any_char+))
             maps:lines(dst:u64 " " src:u64 " " len:u64 => Map { dst, src: range(src, len) })
-            => maps
+            => (name, maps)
         )
-        => Task { seeds, maps }
+        => (seeds, sections)
     );
-    p.parse(input).unwrap()
+    let (seeds, sections) = p.parse(input).unwrap();
+    let (names, maps) = sections
+        .into_iter()
+        .map(|(name, maps)| (name.trim_end_matches(" map:").to_string(), maps))
+        .unzip();
+    Task { seeds, names, maps }
 }
 
-fn location(seed: u64, maps: &Vec<Vec<Map>>) -> u64 {
+fn location(seed: u64, maps: &[Vec<Map>]) -> u64 {
     maps.iter().fold(seed, |x, rules| {
         match rules.iter().find(|&m| m.src.contains(&x)) {
             Some(m) => m.translate(&x),
@@ -82,28 +73,6 @@ pub fn solve_part1(input: &Task) -> u64 {
         .unwrap()
 }
 
-fn bulk_translate(xs: Vec<Range<u64>>, map: &Vec<Map>) -> Vec<Range<u64>> {
-    let mut work: VecDeque<Range<u64>> = xs.into_iter().collect();
-    let mut result = Vec::new();
-    for m in map {
-        let len = work.len();
-        for _ in 0..len {
-            let range = work.pop_front().unwrap();
-            result.extend(m.translate_range(range, &mut work));
-        }
-    }
-    result.extend(work);
-    result
-}
-
-fn locations(seeds: &Vec<Range<u64>>, maps: &Vec<Vec<Map>>) -> Vec<Range<u64>> {
-    let mut prev = seeds.iter().cloned().collect();
-    for m in maps {
-        prev = bulk_translate(prev, m);
-    }
-    prev
-}
-
 #[aoc(day5, part2)]
 pub fn solve_part2(input: &Task) -> u64 {
     let seeds: Vec<Range<u64>> = input
@@ -111,58 +80,569 @@ pub fn solve_part2(input: &Task) -> u64 {
         .chunks(2)
         .map(|def| range(def[0], def[1]))
         .collect();
-    locations(&seeds, &input.maps)
+    input.compose().min_over(&seeds).unwrap()
+}
+
+impl Task {
+    pub fn stages(&self) -> Vec<Piecewise> {
+        self.maps
+            .iter()
+            .map(|stage| Piecewise::stage(stage))
+            .collect()
+    }
+
+    pub fn compose(&self) -> Piecewise {
+        self.stages()
+            .iter()
+            .fold(Piecewise::identity(), |f, stage| f.then(stage))
+    }
+
+    // "seed", "soil", ..., "location", read off the `a-to-b` headers
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories = Vec::new();
+        for (i, name) in self.names.iter().enumerate() {
+            let (from, to) = name.split_once("-to-").unwrap_or((name, name));
+            if i == 0 {
+                categories.push(from);
+            }
+            categories.push(to);
+        }
+        categories
+    }
+
+    // The value of every category a seed passes through, seed first
+    pub fn trace(&self, seed: u64) -> Vec<(&str, u64)> {
+        let values = self.maps.iter().scan(seed, |x, stage| {
+            *x = location(*x, std::slice::from_ref(stage));
+            Some(*x)
+        });
+        self.categories()
+            .into_iter()
+            .zip(std::iter::once(seed).chain(values))
+            .collect()
+    }
+
+    pub fn seeds_for(&self, locations: Range<u64>) -> Vec<Range<u64>> {
+        self.compose().preimage(&[locations])
+    }
+
+    // For each category, seed first, the values on some path into the location range
+    pub fn trace_back(&self, locations: Range<u64>) -> Vec<Vec<Range<u64>>> {
+        let mut ranges = vec![vec![locations]];
+        for stage in self.stages().iter().rev() {
+            let earlier = stage.preimage(ranges.last().unwrap());
+            ranges.push(earlier);
+        }
+        ranges.reverse();
+        ranges
+    }
+}
+
+// A function on u64 that adds a constant shift on each piece. Piece i starts at
+// breaks[i] and runs up to the next break; the last one runs to the end of the type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Piecewise {
+    breaks: Vec<u64>,
+    shifts: Vec<i128>,
+}
+
+impl Piecewise {
+    pub fn identity() -> Piecewise {
+        Piecewise {
+            breaks: vec![0],
+            shifts: vec![0],
+        }
+    }
+
+    // Where source ranges overlap, the first map listed wins, as in `location`
+    pub fn stage(maps: &[Map]) -> Piecewise {
+        let mut cuts: Vec<u64> = maps
+            .iter()
+            .flat_map(|m| [m.src.start, m.src.end])
+            .chain([0])
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        let pieces = cuts.into_iter().map(|x| {
+            let shift = maps
+                .iter()
+                .find(|m| m.src.contains(&x))
+                .map_or(0, Map::shift);
+            (x, shift)
+        });
+        Piecewise::from_pieces(pieces)
+    }
+
+    fn from_pieces<I: IntoIterator<Item = (u64, i128)>>(pieces: I) -> Piecewise {
+        let mut f = Piecewise {
+            breaks: Vec::new(),
+            shifts: Vec::new(),
+        };
+        for (start, shift) in pieces {
+            if f.shifts.last() != Some(&shift) {
+                f.breaks.push(start);
+                f.shifts.push(shift);
+            }
+        }
+        f
+    }
+
+    fn end(&self, i: usize) -> i128 {
+        self.breaks.get(i + 1).map_or(1 << 64, |&b| b as i128)
+    }
+
+    fn piece(&self, x: u64) -> usize {
+        self.breaks.partition_point(|&b| b <= x) - 1
+    }
+
+    pub fn breakpoints(&self) -> &[u64] {
+        &self.breaks
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = (Range<u64>, i128)> + '_ {
+        (0..self.breaks.len()).map(|i| {
+            let end = min(self.end(i), u64::MAX as i128) as u64;
+            (self.breaks[i]..end, self.shifts[i])
+        })
+    }
+
+    pub fn apply(&self, x: u64) -> u64 {
+        let y = x as i128 + self.shifts[self.piece(x)];
+        u64::try_from(y).expect("value leaves u64")
+    }
+
+    // Sorted, disjoint ranges of every x with f(x) in one of the given ranges
+    pub fn preimage(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut found: Vec<Range<u64>> = Vec::new();
+        for (i, &shift) in self.shifts.iter().enumerate() {
+            let (start, end) = (self.breaks[i] as i128, self.end(i));
+            for r in ranges {
+                let lo = max(start, r.start as i128 - shift);
+                let hi = min(end, r.end as i128 - shift);
+                if lo < hi {
+                    found.push(lo as u64..min(hi, u64::MAX as i128) as u64);
+                }
+            }
+        }
+        found.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<u64>> = Vec::new();
+        for r in found {
+            match merged.last_mut() {
+                Some(last) if last.end >= r.start => last.end = max(last.end, r.end),
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
+
+    // Defined only when the pieces map onto the whole type exactly once
+    pub fn inverse(&self) -> Option<Piecewise> {
+        let mut images: Vec<(i128, i128, i128)> = (0..self.breaks.len())
+            .map(|i| {
+                let shift = self.shifts[i];
+                (self.breaks[i] as i128 + shift, self.end(i) + shift, shift)
+            })
+            .collect();
+        images.sort_unstable();
+        let mut expected = 0;
+        for &(start, end, _) in images.iter() {
+            if start != expected {
+                return None;
+            }
+            expected = end;
+        }
+        if expected != 1 << 64 {
+            return None;
+        }
+        Some(Piecewise::from_pieces(
+            images
+                .into_iter()
+                .map(|(start, _, shift)| (start as u64, -shift)),
+        ))
+    }
+
+    // `next` applied after `self`
+    pub fn then(&self, next: &Piecewise) -> Piecewise {
+        let mut pieces = Vec::new();
+        for (i, &shift) in self.shifts.iter().enumerate() {
+            let (start, end) = (self.breaks[i] as i128, self.end(i));
+            let mut x = start;
+            while x < end {
+                let y = x + shift;
+                let j = next.piece(y.clamp(0, u64::MAX as i128) as u64);
+                pieces.push((x as u64, shift + next.shifts[j]));
+                x = match next.breaks.get(j + 1) {
+                    Some(&b) => min(end, b as i128 - shift),
+                    None => end,
+                };
+            }
+        }
+        Piecewise::from_pieces(pieces)
+    }
+
+    // The function is increasing on each piece, so only piece starts need checking
+    pub fn min_over(&self, ranges: &[Range<u64>]) -> Option<u64> {
+        let mut best = None;
+        for r in ranges.iter().filter(|r| !r.is_empty()) {
+            let mut i = self.piece(r.start);
+            while i < self.breaks.len() && (self.breaks[i] as u128) < r.end as u128 {
+                let y = self.apply(max(r.start, self.breaks[i]));
+                best = Some(best.map_or(y, |b| min(b, y)));
+                i += 1;
+            }
+        }
+        best
+    }
+
+    // As `dst src len` lines; identity pieces are left out, as the almanac leaves them
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        for i in 0..self.breaks.len() {
+            if self.shifts[i] != 0 {
+                let start = self.breaks[i] as i128;
+                let len = self.end(i) - start;
+                writeln!(out, "{} {} {}", start + self.shifts[i], start, len).unwrap();
+            }
+        }
+        out
+    }
+}
+
+// Lines are numbered from 1; `with` is the earlier line a map clashes with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    Malformed {
+        line: usize,
+    },
+    Header {
+        line: usize,
+        text: String,
+    },
+    Chain {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+    Overflow {
+        line: usize,
+    },
+    SourceOverlap {
+        line: usize,
+        with: usize,
+    },
+    DestinationOverlap {
+        line: usize,
+        with: usize,
+    },
+    Gap {
+        section: String,
+        range: Range<u64>,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Issue::Malformed { line } => write!(f, "line {}: not a `dst src len` map", line),
+            Issue::Header { line, text } => {
+                write!(
+                    f,
+                    "line {}: {:?} is not an `a-to-b map:` header",
+                    line, text
+                )
+            }
+            Issue::Chain {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: maps from {} where {} was expected",
+                line, found, expected
+            ),
+            Issue::Overflow { line } => write!(f, "line {}: range runs past u64", line),
+            Issue::SourceOverlap { line, with } => {
+                write!(f, "line {}: source range overlaps line {}", line, with)
+            }
+            Issue::DestinationOverlap { line, with } => {
+                write!(f, "line {}: destination range overlaps line {}", line, with)
+            }
+            Issue::Gap { section, range } => {
+                write!(
+                    f,
+                    "{}: {}..{} is not mapped",
+                    section, range.start, range.end
+                )
+            }
+        }
+    }
+}
+
+struct Entry {
+    line: usize,
+    dst: u128,
+    src: u128,
+    len: u128,
+}
+
+fn overlap(a: u128, b: u128, len_a: u128, len_b: u128) -> bool {
+    max(a, b) < min(a + len_a, b + len_b)
+}
+
+fn check_section(section: &str, entries: &[Entry], issues: &mut Vec<Issue>) {
+    for (k, e) in entries.iter().enumerate() {
+        let limit = u64::MAX as u128;
+        if e.src + e.len > limit || e.dst + e.len > limit {
+            issues.push(Issue::Overflow { line: e.line });
+        }
+        for prev in entries[..k].iter() {
+            if overlap(prev.src, e.src, prev.len, e.len) {
+                issues.push(Issue::SourceOverlap {
+                    line: e.line,
+                    with: prev.line,
+                });
+            }
+            if overlap(prev.dst, e.dst, prev.len, e.len) {
+                issues.push(Issue::DestinationOverlap {
+                    line: e.line,
+                    with: prev.line,
+                });
+            }
+        }
+    }
+    let mut sources: Vec<(u128, u128)> = entries
         .iter()
-        .map(|r| r.start)
-        .min()
-        .unwrap()
+        .filter(|e| e.len > 0)
+        .map(|e| (e.src, e.src + e.len))
+        .collect();
+    sources.sort_unstable();
+    let mut covered = sources.first().map(|&(start, _)| start);
+    for (start, end) in sources {
+        let reached = covered.unwrap();
+        if start > reached {
+            issues.push(Issue::Gap {
+                section: section.to_string(),
+                range: reached as u64..start as u64,
+            });
+        }
+        covered = Some(max(reached, end));
+    }
+}
+
+// Reads the raw almanac leniently and reports everything `input_generator` would
+// either reject or quietly resolve by map order
+pub fn check(input: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut expected = "seed".to_string();
+    let mut section: Option<String> = None;
+    let mut entries: Vec<Entry> = Vec::new();
+    for (i, text) in input.lines().enumerate() {
+        let line = i + 1;
+        let text = text.trim();
+        if text.is_empty() || text.starts_with("seeds:") {
+            continue;
+        }
+        if let Some(header) = text.strip_suffix(" map:") {
+            if let Some(name) = section.replace(header.to_string()) {
+                check_section(&name, &entries, &mut issues);
+            }
+            entries.clear();
+            match header.split_once("-to-") {
+                Some((from, to)) => {
+                    if from != expected {
+                        issues.push(Issue::Chain {
+                            line,
+                            expected: expected.clone(),
+                            found: from.to_string(),
+                        });
+                    }
+                    expected = to.to_string();
+                }
+                None => issues.push(Issue::Header {
+                    line,
+                    text: text.to_string(),
+                }),
+            }
+            continue;
+        }
+        let numbers: Vec<u128> = text
+            .split_whitespace()
+            .map_while(|n| n.parse::<u64>().ok().map(u128::from))
+            .collect();
+        match numbers[..] {
+            [dst, src, len] if text.split_whitespace().count() == 3 && section.is_some() => entries
+                .push(Entry {
+                    line,
+                    dst,
+                    src,
+                    len,
+                }),
+            _ => issues.push(Issue::Malformed { line }),
+        }
+    }
+    if let Some(name) = section {
+        check_section(&name, &entries, &mut issues);
+    }
+    issues
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::fixture;
+
+    #[test]
+    fn composed() {
+        let task = input_generator(&fixture(5, "example"));
+        let f = task.compose();
+        for seed in 0..120 {
+            assert_eq!(f.apply(seed), location(seed, &task.maps));
+        }
+        assert_eq!(f.apply(u64::MAX - 1), u64::MAX - 1);
+        assert!(f.breakpoints().windows(2).all(|w| w[0] < w[1]));
+        let covered: u64 = f.pieces().map(|(r, _)| r.end - r.start).sum();
+        assert_eq!(covered, u64::MAX);
+
+        assert_eq!(f.min_over(&[79..93, 55..68]), Some(46));
+        assert_eq!(f.min_over(&[79..80]), Some(82));
+        assert_eq!(f.min_over(&[]), None);
+
+        let reparsed = input_generator(&format!(
+            "seeds: 79 14 55 13\n\nseed-to-location map:\n{}",
+            f.dump().trim()
+        ));
+        assert_eq!(reparsed.compose(), f);
+        assert_eq!(solve_part1(&reparsed), 35);
+        assert_eq!(solve_part2(&reparsed), 46);
+    }
+
+    #[test]
+    fn inverse() {
+        let task = input_generator(&fixture(5, "example"));
+        let f = task.compose();
+        let g = f.inverse().unwrap();
+        for seed in (0..200).chain([1 << 40, u64::MAX - 1]) {
+            assert_eq!(g.apply(f.apply(seed)), seed);
+        }
+        for stage in task.stages() {
+            assert!(stage.inverse().is_some());
+        }
+        let clash = Piecewise::stage(&[Map {
+            dst: 0,
+            src: 10..20,
+        }]);
+        assert_eq!(clash.inverse(), None);
+        assert_eq!(clash.preimage(&[5..12]), vec![5..10, 15..20]);
+
+        assert_eq!(task.seeds_for(46..47), vec![82..83]);
+    }
+
+    #[test]
+    fn provenance() {
+        let task = input_generator(&fixture(5, "example"));
+        assert_eq!(
+            task.trace(79),
+            vec![
+                ("seed", 79),
+                ("soil", 81),
+                ("fertilizer", 81),
+                ("water", 81),
+                ("light", 74),
+                ("temperature", 78),
+                ("humidity", 78),
+                ("location", 82),
+            ]
+        );
+        let back = task.trace_back(82..83);
+        assert_eq!(back.len(), 8);
+        assert_eq!(back[0], vec![79..80]);
+        assert_eq!(back[4], vec![74..75]);
+        assert_eq!(back[7], vec![82..83]);
+    }
 
     #[test]
-    fn example() {
-        let input = r#"
-seeds: 79 14 55 13
+    fn consistency() {
+        assert!(check(&fixture(5, "example")).is_empty());
+        let broken = r#"seeds: 1 2
 
 seed-to-soil map:
 50 98 2
-52 50 48
+52 90 10
+60 20 5
 
-soil-to-fertilizer map:
-0 15 37
-37 52 2
-39 0 15
+soil-water map:
+1 2
 
 fertilizer-to-water map:
-49 53 8
-0 11 42
-42 0 7
-57 7 4
-
-water-to-light map:
-88 18 7
-18 25 70
-
-light-to-temperature map:
-45 77 23
-81 45 19
-68 64 13
-
-temperature-to-humidity map:
-0 69 1
-1 0 69
-
-humidity-to-location map:
-60 56 37
-56 93 4"#
-            .trim();
-        let parsed = input_generator(input);
-        let answer = solve_part1(&parsed);
-        assert_eq!(answer, 35);
-        let answer2 = solve_part2(&parsed);
-        assert_eq!(answer2, 46);
+18446744073709551610 0 10
+1 18446744073709551615 1
+"#;
+        let issues = check(broken);
+        assert_eq!(
+            issues,
+            vec![
+                Issue::SourceOverlap { line: 5, with: 4 },
+                Issue::DestinationOverlap { line: 6, with: 5 },
+                Issue::Gap {
+                    section: "seed-to-soil".to_string(),
+                    range: 25..90
+                },
+                Issue::Header {
+                    line: 8,
+                    text: "soil-water map:".to_string()
+                },
+                Issue::Malformed { line: 9 },
+                Issue::Chain {
+                    line: 11,
+                    expected: "soil".to_string(),
+                    found: "fertilizer".to_string()
+                },
+                Issue::Overflow { line: 12 },
+                Issue::Overflow { line: 13 },
+                Issue::Gap {
+                    section: "fertilizer-to-water".to_string(),
+                    range: 10..u64::MAX
+                },
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "line 5: source range overlaps line 4"
+        );
+    }
+
+    #[test]
+    fn top_of_range() {
+        // Seeds past 2^63 used to overflow while translating, though the map is valid
+        let input = "seeds: 9223372036854775809 9223372036854775819\n\n\
+                     seed-to-soil map:\n\
+                     9223372036854775808 9223372036854775808 10\n\
+                     5 9223372036854775818 2";
+        assert!(check(input).is_empty());
+        let task = input_generator(input);
+        assert_eq!(
+            location(9223372036854775809, &task.maps),
+            9223372036854775809
+        );
+        assert_eq!(solve_part1(&task), 6);
+    }
+
+    #[test]
+    fn first_map_wins() {
+        let stage = vec![
+            Map {
+                dst: 100,
+                src: 0..10,
+            },
+            Map {
+                dst: 200,
+                src: 5..15,
+            },
+        ];
+        let f = Piecewise::stage(&stage);
+        assert_eq!(f.breakpoints(), [0, 10, 15]);
+        assert_eq!(f.apply(7), 107);
+        assert_eq!(f.apply(12), 207);
+        assert_eq!(f.apply(15), 15);
     }
 }
@@ -1,69 +1,239 @@
+use std::ops::RangeInclusive;
+
 use aoc_parse::{parser, prelude::*};
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::linalg::{self, Wide};
 
 #[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> (Vec<u32>, Vec<u32>) {
+pub fn input_generator(input: &str) -> (Vec<String>, Vec<String>) {
     let p = parser!(
-        line("Time:" " "+ times:repeat_sep(u32, " "+) => times)
-        line("Distance:" " "+ distances:repeat_sep(u32, " "+) => distances)
+        line("Time:" " "+ times:repeat_sep(string(digit+), " "+) => times)
+        line("Distance:" " "+ distances:repeat_sep(string(digit+), " "+) => distances)
     );
     p.parse(input).unwrap()
 }
 
-fn number_of_ways(t: u64, d: u64) -> u64 {
-    let mid = t / 2;
-    if mid * (t - mid) <= d {
-        0
-    } else {
-        let discriminant = (t * t) as f64 - 4.0 * d as f64;
-        let sqrt_disc = discriminant.sqrt();
-        let root1 = (t as f64 - sqrt_disc) / 2.0;
-        let root2 = (t as f64 + sqrt_disc) / 2.0;
-
-        let first = (root1 + 1.0).floor();
-        let last = (root2 - 1.0).ceil();
-
-        if last >= first {
-            (1.0 + last - first) as u64
-        } else {
-            0
-        }
+// Hold times that beat the record; they never exceed the race time, so they fit in T
+pub fn winning_holds<T: Wide>(time: &T, distance: &T) -> Option<RangeInclusive<T>> {
+    linalg::quadratic_interior(time, distance)
+}
+
+pub fn number_of_ways<T: Wide>(time: &T, distance: &T) -> T {
+    match winning_holds(time, distance) {
+        Some(holds) => holds.end().clone() - holds.start().clone() + T::one(),
+        None => T::zero(),
     }
 }
 
-fn concat(nrs: &Vec<u32>) -> u64 {
-    nrs.iter().fold(0, |acc, &t| {
-        acc * 10u64.pow(t.to_string().len() as u32) + t as u64
-    })
+fn parse<T: Wide>(digits: &str) -> T {
+    T::from_str_radix(digits, 10).ok().unwrap()
 }
 
 #[aoc(day6, part1)]
-pub fn solve_part1((times, distances): &(Vec<u32>, Vec<u32>)) -> u64 {
-    times
-        .iter()
-        .zip(distances)
-        .map(|(&t, &d)| number_of_ways(t as u64, d as u64))
-        .product()
+pub fn solve_part1(input: &(Vec<String>, Vec<String>)) -> BigUint {
+    product_of_ways(&Linear, input)
 }
 
 #[aoc(day6, part2)]
-pub fn solve_part2((times, distances): &(Vec<u32>, Vec<u32>)) -> u64 {
-    let time = concat(times);
-    let distance = concat(distances);
-    // println!("{}, {}", time, distance);
-    number_of_ways(time, distance)
+pub fn solve_part2((times, distances): &(Vec<String>, Vec<String>)) -> BigUint {
+    let time: BigUint = parse(&times.concat());
+    let distance: BigUint = parse(&distances.concat());
+    number_of_ways(&time, &distance)
+}
+
+pub fn races(input: &(Vec<String>, Vec<String>)) -> Vec<(BigUint, BigUint)> {
+    input
+        .0
+        .iter()
+        .zip(&input.1)
+        .map(|(t, d)| (parse(t), parse(d)))
+        .collect()
+}
+
+// Distance must rise and then fall with the hold time, never rising again after
+// a drop or a level step; that is what the default searches rely on
+pub trait BoatModel {
+    fn distance(&self, hold: &BigUint, time: &BigUint) -> BigUint;
+
+    // The earliest hold reaching the longest distance
+    fn optimal_hold(&self, time: &BigUint) -> BigUint {
+        let one = BigUint::from(1u32);
+        first(BigUint::zero(), time.clone(), |h| {
+            *h == *time || self.distance(&(h + &one), time) <= self.distance(h, time)
+        })
+    }
+
+    fn winning_holds(&self, time: &BigUint, record: &BigUint) -> Option<RangeInclusive<BigUint>> {
+        let best = self.optimal_hold(time);
+        if self.distance(&best, time) <= *record {
+            return None;
+        }
+        let lo = first(BigUint::zero(), best.clone(), |h| {
+            self.distance(h, time) > *record
+        });
+        let past = first(best, time.clone() + 1u32, |h| {
+            h > time || self.distance(h, time) <= *record
+        });
+        Some(lo..=past - 1u32)
+    }
+
+    fn number_of_ways(&self, time: &BigUint, record: &BigUint) -> BigUint {
+        match self.winning_holds(time, record) {
+            Some(holds) => holds.end() - holds.start() + 1u32,
+            None => BigUint::zero(),
+        }
+    }
+}
+
+// Smallest x in lo..=hi where the monotone predicate holds, assuming it holds at hi
+fn first<P: Fn(&BigUint) -> bool>(mut lo: BigUint, mut hi: BigUint, pred: P) -> BigUint {
+    while lo < hi {
+        let mid = (&lo + &hi) / 2u32;
+        if pred(&mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1u32;
+        }
+    }
+    lo
+}
+
+// The puzzle's boat: every millisecond held adds one to the speed
+pub struct Linear;
+
+impl BoatModel for Linear {
+    fn distance(&self, hold: &BigUint, time: &BigUint) -> BigUint {
+        hold * (time - hold)
+    }
+
+    fn winning_holds(&self, time: &BigUint, record: &BigUint) -> Option<RangeInclusive<BigUint>> {
+        winning_holds(time, record)
+    }
+}
+
+pub struct ChargeRate(pub u64);
+
+impl BoatModel for ChargeRate {
+    fn distance(&self, hold: &BigUint, time: &BigUint) -> BigUint {
+        hold * self.0 * (time - hold)
+    }
+}
+
+// Speed grows with the square of the hold time
+pub struct QuadraticCharge;
+
+impl BoatModel for QuadraticCharge {
+    fn distance(&self, hold: &BigUint, time: &BigUint) -> BigUint {
+        hold * hold * (time - hold)
+    }
+}
+
+// Linear charging, but the speed stops growing at the cap
+pub struct SpeedCap(pub u64);
+
+impl BoatModel for SpeedCap {
+    fn distance(&self, hold: &BigUint, time: &BigUint) -> BigUint {
+        hold.min(&BigUint::from(self.0)) * (time - hold)
+    }
+}
+
+pub fn product_of_ways<M: BoatModel>(model: &M, input: &(Vec<String>, Vec<String>)) -> BigUint {
+    races(input)
+        .iter()
+        .map(|(t, d)| model.number_of_ways(t, d))
+        .product()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::fixture;
+    use num_traits::ToPrimitive;
 
     #[test]
-    fn example() {
-        let input = "Time:      7  15   30\nDistance:  9  40  200";
+    fn widths_agree() {
+        assert_eq!(winning_holds(&71530u64, &940200u64), Some(14..=71516));
+        assert_eq!(winning_holds(&71530u128, &940200u128), Some(14..=71516));
+        assert_eq!(
+            winning_holds(&BigUint::from(30u32), &BigUint::from(200u32)),
+            Some(BigUint::from(11u32)..=BigUint::from(19u32))
+        );
+        assert_eq!(number_of_ways(&4u64, &4u64), 0);
+        // (t / 2)^2 is far past u64, so the old float and u64 versions lost it
+        let t = u64::MAX - 1;
+        let half = t as u128 / 2;
+        assert_eq!(winning_holds(&t, &0), Some(1..=t - 1));
+        assert_eq!(number_of_ways(&(t as u128), &(half * half - 1)), 1);
+    }
+
+    #[test]
+    fn forty_digits() {
+        let input = "Time:      20000000000 0000000000\n\
+                     Distance:  99999999999999999999 99999999999999999999";
         let parsed = input_generator(input);
-        let answer1 = solve_part1(&parsed);
-        assert_eq!(answer1, 288);
-        let answer2 = solve_part2(&parsed);
-        assert_eq!(answer2, 71503);
+        let time: BigUint = parse(&parsed.0.concat());
+        let distance: BigUint = parse(&parsed.1.concat());
+        assert_eq!(distance.to_string().len(), 40);
+        // distance is (t / 2)^2 - 1, beaten only by holding for exactly half the race
+        let half = time.clone() / 2u32;
+        assert_eq!(distance, half.clone() * half.clone() - 1u32);
+        assert_eq!(winning_holds(&time, &distance), Some(half.clone()..=half));
+        assert_eq!(solve_part2(&parsed), BigUint::from(1u32));
+        let zero = BigUint::zero();
+        assert_eq!(number_of_ways(&time, &zero), time - 1u32);
+    }
+
+    #[test]
+    fn forty_digit_races() {
+        // The example's first race, one only the half-time hold wins, one every inner hold wins
+        let time = format!("2{}", "0".repeat(39));
+        let record = "9".repeat(78);
+        let input = format!("Time: 7 {} {}\nDistance: 9 {} 0", time, time, record);
+        let ways = BigUint::from(4u32) * (parse::<BigUint>(&time) - 1u32);
+        assert_eq!(solve_part1(&input_generator(&input)), ways);
+        assert_eq!(ways.to_string(), format!("7{}6", "9".repeat(38)));
+    }
+
+    fn brute_force<M: BoatModel>(model: &M, t: u32, d: u32) -> (u32, u32) {
+        let (t, d) = (BigUint::from(t), BigUint::from(d));
+        let distances: Vec<BigUint> = (0..=t.to_u32().unwrap())
+            .map(|h| model.distance(&BigUint::from(h), &t))
+            .collect();
+        let best = distances.iter().max().unwrap();
+        let optimal = distances.iter().position(|x| x == best).unwrap() as u32;
+        (distances.iter().filter(|&x| *x > d).count() as u32, optimal)
+    }
+
+    fn agrees<M: BoatModel>(model: &M) {
+        for t in 0..40 {
+            for d in (0..300).step_by(7) {
+                let (ways, optimal) = brute_force(model, t, d);
+                let (time, record) = (BigUint::from(t), BigUint::from(d));
+                assert_eq!(model.number_of_ways(&time, &record), BigUint::from(ways));
+                assert_eq!(model.optimal_hold(&time), BigUint::from(optimal));
+            }
+        }
+    }
+
+    #[test]
+    fn boat_models() {
+        agrees(&Linear);
+        agrees(&ChargeRate(3));
+        agrees(&QuadraticCharge);
+        agrees(&SpeedCap(4));
+        agrees(&SpeedCap(0));
+
+        let parsed = input_generator(&fixture(6, "example"));
+        assert_eq!(product_of_ways(&Linear, &parsed), BigUint::from(288u32));
+        let (t, d) = (BigUint::from(7u32), BigUint::from(9u32));
+        assert_eq!(ChargeRate(2).number_of_ways(&t, &d), BigUint::from(6u32));
+        assert_eq!(
+            QuadraticCharge.winning_holds(&t, &d),
+            Some(BigUint::from(2u32)..=BigUint::from(6u32))
+        );
+        assert_eq!(QuadraticCharge.optimal_hold(&t), BigUint::from(5u32));
+        assert_eq!(SpeedCap(2).number_of_ways(&t, &d), BigUint::from(1u32));
     }
 }
@@ -1,25 +1,35 @@
+use std::fmt::{self, Write};
+
 use aoc_parse::{parser, prelude::*};
 
+use crate::{checked, stream::Accumulator};
+
 const CARDS: &str = "AKQJT98765432";
 const CARDS2: &str = "AKQT98765432J";
-const N: usize = CARDS.len();
 
 #[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Copy, Clone)]
-enum HandRank {
+pub enum HandRank {
     HighCard,
     OnePair,
     TwoPair,
     ThreeOfAKind,
+    // Straights and flushes only occur in poker mode
+    Straight,
+    Flush,
     FullHouse,
     FourOfAKind,
+    StraightFlush,
     FiveOfAKind,
 }
 
-fn rank_for(top_two: (usize, usize)) -> HandRank {
-    match top_two {
-        (5, _) => HandRank::FiveOfAKind,
+// The best five-card category the groups make, so longer hands are named by their best five
+fn rank_for(signature: &[usize]) -> HandRank {
+    let top = signature.first().copied().unwrap_or(0);
+    let second = signature.get(1).copied().unwrap_or(0);
+    match (top, second) {
+        (5.., _) => HandRank::FiveOfAKind,
         (4, _) => HandRank::FourOfAKind,
-        (3, 2) => HandRank::FullHouse,
+        (3, 2..) => HandRank::FullHouse,
         (3, _) => HandRank::ThreeOfAKind,
         (2, 2) => HandRank::TwoPair,
         (2, _) => HandRank::OnePair,
@@ -27,94 +37,610 @@ fn rank_for(top_two: (usize, usize)) -> HandRank {
     }
 }
 
-fn card_rank(cards: &str, card: char) -> usize {
-    cards
-        .chars()
-        .rev()
-        .enumerate()
-        .find_map(|(i, c)| if c == card { Some(i) } else { None })
-        .unwrap()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    // Card by card in the order dealt, as Camel Cards does
+    Positional,
+    // Bigger groups first, then stronger cards, like poker kickers
+    Grouped,
+}
+
+// Hands compare by signature (group sizes, largest first) and then by the tie-break
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HandKey {
+    pub signature: Vec<usize>,
+    pub tie: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    // Weakest first, so a card's strength is its index
+    deck: Vec<char>,
+    wilds: Vec<char>,
+    hand_len: usize,
+    tie_break: TieBreak,
+}
+
+impl Ruleset {
+    // The deck is listed strongest first, as in the puzzle text
+    pub fn new(deck: &str, wilds: &str, hand_len: usize, tie_break: TieBreak) -> Ruleset {
+        Ruleset {
+            deck: deck.chars().rev().collect(),
+            wilds: wilds.chars().collect(),
+            hand_len,
+            tie_break,
+        }
+    }
+
+    pub fn camel() -> Ruleset {
+        Ruleset::new(CARDS, "", 5, TieBreak::Positional)
+    }
+
+    pub fn jokers() -> Ruleset {
+        Ruleset::new(CARDS2, "J", 5, TieBreak::Positional)
+    }
+
+    pub fn deck_size(&self) -> usize {
+        self.deck.len()
+    }
+
+    pub fn hand_len(&self) -> usize {
+        self.hand_len
+    }
+
+    fn strength(&self, card: char) -> usize {
+        self.deck
+            .iter()
+            .position(|&c| c == card)
+            .unwrap_or_else(|| panic!("{} is not in the deck", card))
+    }
+
+    fn strengths(&self, hand: &str) -> Vec<usize> {
+        let cards: Vec<usize> = hand.chars().map(|c| self.strength(c)).collect();
+        assert_eq!(cards.len(), self.hand_len, "{} is not a full hand", hand);
+        cards
+    }
+
+    // How many of each card, indexed by strength
+    pub fn counts(&self, hand: &str) -> Vec<usize> {
+        self.tally(&self.strengths(hand))
+    }
+
+    fn tally(&self, cards: &[usize]) -> Vec<usize> {
+        let mut counts = vec![0; self.deck.len()];
+        for &card in cards {
+            counts[card] += 1;
+        }
+        counts
+    }
+
+    // Wild cards all join the largest natural group, which is always the best use of them
+    pub fn signature(&self, hand: &str) -> Vec<usize> {
+        self.group(&self.counts(hand))
+    }
+
+    fn group(&self, counts: &[usize]) -> Vec<usize> {
+        let mut wild = 0;
+        let mut groups: Vec<usize> = Vec::new();
+        for (card, &n) in counts.iter().enumerate() {
+            if self.wilds.contains(&self.deck[card]) {
+                wild += n;
+            } else if n > 0 {
+                groups.push(n);
+            }
+        }
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        match groups.first_mut() {
+            Some(top) => *top += wild,
+            None => groups.push(wild),
+        }
+        groups
+    }
+
+    fn hand_key(&self, hand: &str) -> HandKey {
+        let cards = self.strengths(hand);
+        let counts = self.tally(&cards);
+        let tie = match self.tie_break {
+            TieBreak::Positional => cards,
+            TieBreak::Grouped => {
+                let mut cards: Vec<usize> = (0..counts.len()).filter(|&c| counts[c] > 0).collect();
+                cards.sort_unstable_by_key(|&c| std::cmp::Reverse((counts[c], c)));
+                cards
+            }
+        };
+        HandKey {
+            signature: self.group(&counts),
+            tie,
+        }
+    }
+
+    pub fn category(&self, hand: &str) -> HandRank {
+        rank_for(&self.signature(hand))
+    }
+
+    // The hand read as a number in base deck size, which orders hands positionally
+    pub fn value(&self, hand: &str) -> u64 {
+        let n = self.deck.len() as u64;
+        self.strengths(hand).into_iter().fold(0, |value, card| {
+            checked::add(
+                7,
+                "hand value",
+                checked::mul(7, "hand value", value, n),
+                card as u64,
+            )
+        })
+    }
+
+    // Inverse of `value`
+    pub fn decode(&self, mut value: u64) -> String {
+        let n = self.deck.len() as u64;
+        let mut cards: Vec<char> = (0..self.hand_len)
+            .map(|_| {
+                let card = self.deck[(value % n) as usize];
+                value /= n;
+                card
+            })
+            .collect();
+        cards.reverse();
+        cards.into_iter().collect()
+    }
+
+    // The natural card the wild cards stand in for: the largest group, the stronger on a tie
+    fn wild_target(&self, counts: &[usize]) -> Option<char> {
+        if !counts
+            .iter()
+            .enumerate()
+            .any(|(c, &n)| n > 0 && self.wilds.contains(&self.deck[c]))
+        {
+            return None;
+        }
+        let natural = (0..counts.len()).filter(|&c| !self.wilds.contains(&self.deck[c]));
+        natural
+            .max_by_key(|&c| (counts[c], c))
+            .map(|c| self.deck[c])
+    }
+
+    pub fn explain_hand(&self, hand: &str) -> Explanation {
+        let key = self.hand_key(hand);
+        let counts = self.counts(hand);
+        let target = self.wild_target(&counts);
+        let mut cards: Vec<(char, usize)> = (0..counts.len())
+            .rev()
+            .filter(|&c| counts[c] > 0)
+            .map(|c| (self.deck[c], counts[c]))
+            .collect();
+        cards.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        let wilds = match target {
+            Some(to) => cards
+                .iter()
+                .filter(|(c, _)| self.wilds.contains(c))
+                .map(|&(c, n)| (c, n, to))
+                .collect(),
+            None => Vec::new(),
+        };
+        Explanation {
+            hand: hand.to_string(),
+            category: rank_for(&key.signature),
+            signature: key.signature,
+            counts: cards,
+            wilds,
+            tie: key.tie.iter().map(|&c| self.deck[c]).collect(),
+            value: self.value(hand),
+        }
+    }
+
+    // Every bid weakest first with what it wins, as a table
+    pub fn explain(&self, bids: &[(String, u64)]) -> String {
+        let mut out = String::new();
+        let mut total = 0;
+        for (i, (hand, stake)) in self.ranked(bids).into_iter().enumerate() {
+            let rank = i as u64 + 1;
+            let won = checked::mul(7, "winnings", rank, *stake);
+            total = checked::add(7, "winnings", total, won);
+            writeln!(
+                out,
+                "{:>5} | {:>6} x {:<5} = {:>8} | {}",
+                rank,
+                stake,
+                rank,
+                won,
+                self.explain_hand(hand)
+            )
+            .unwrap();
+        }
+        writeln!(out, "total {}", total).unwrap();
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub hand: String,
+    pub category: HandRank,
+    pub signature: Vec<usize>,
+    // Strongest first within each group size, biggest groups first
+    pub counts: Vec<(char, usize)>,
+    // Each wild card, how many of it, and the card it was counted as
+    pub wilds: Vec<(char, usize, char)>,
+    // The cards in the order they break ties
+    pub tie: String,
+    // The positional value, which `Ruleset::decode` turns back into the hand
+    pub value: u64,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:?} {:?}", self.hand, self.category, self.signature)?;
+        let counts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(c, n)| format!("{}x{}", n, c))
+            .collect();
+        write!(f, " counts {}", counts.join(" "))?;
+        for (wild, n, to) in self.wilds.iter() {
+            write!(f, " {}x{}->{}", n, wild, to)?;
+        }
+        write!(f, " tie {} ({})", self.tie, self.value)
+    }
+}
+
+pub trait Scoring {
+    type Key: Ord;
+
+    fn key(&self, hand: &str) -> Self::Key;
+
+    // Weakest first, so a bid's rank is its position plus one
+    fn ranked<'a>(&self, bids: &'a [(String, u64)]) -> Vec<&'a (String, u64)> {
+        let mut ranked: Vec<(Self::Key, &(String, u64))> =
+            bids.iter().map(|bid| (self.key(&bid.0), bid)).collect();
+        ranked.sort_by(|a, b| a.0.cmp(&b.0));
+        ranked.into_iter().map(|(_, bid)| bid).collect()
+    }
+
+    fn winnings(&self, bids: &[(String, u64)]) -> u64 {
+        checked::sum(
+            7,
+            "winnings",
+            self.ranked(bids)
+                .iter()
+                .enumerate()
+                .map(|(i, (_, stake))| checked::mul(7, "winnings", *stake, 1 + i as u64)),
+        )
+    }
+}
+
+impl Scoring for Ruleset {
+    type Key = HandKey;
+
+    fn key(&self, hand: &str) -> HandKey {
+        self.hand_key(hand)
+    }
+}
+
+const SUITS: &str = "cdhs";
+
+// Standard five-card poker: hands are written rank then suit, as in `AhKd9c9s2h`
+pub struct Poker {
+    ranks: Ruleset,
+}
+
+impl Default for Poker {
+    fn default() -> Poker {
+        Poker {
+            ranks: Ruleset::new(CARDS, "", 5, TieBreak::Grouped),
+        }
+    }
 }
 
-fn card_counts(cards: &str, hand: &str) -> ([usize; 13], u64) {
-    let mut counts: [usize; N] = [0; N];
-    let mut value: u64 = 0;
-    for c in hand.chars() {
-        let card = card_rank(cards, c);
-        counts[card] += 1;
-        value = value * N as u64 + card as u64;
+impl Poker {
+    // (rank strength, suit) per card
+    pub fn parse_hand(&self, hand: &str) -> Vec<(usize, usize)> {
+        let chars: Vec<char> = hand.chars().collect();
+        let cards: Vec<(usize, usize)> = chars
+            .chunks(2)
+            .map(|card| {
+                let suit = card.get(1).and_then(|&s| SUITS.find(s));
+                let suit = suit.unwrap_or_else(|| panic!("{} has a card without a suit", hand));
+                (self.ranks.strength(card[0]), suit)
+            })
+            .collect();
+        assert_eq!(cards.len(), 5, "{} is not a five-card hand", hand);
+        cards
+    }
+
+    // The category, then the ranks that break ties in the order they are compared
+    pub fn classify(&self, cards: &[(usize, usize)]) -> (HandRank, Vec<usize>) {
+        let ranks: Vec<usize> = cards.iter().map(|&(rank, _)| rank).collect();
+        let counts = self.ranks.tally(&ranks);
+        let mut kickers: Vec<usize> = (0..counts.len()).filter(|&r| counts[r] > 0).collect();
+        kickers.sort_unstable_by_key(|&r| std::cmp::Reverse((counts[r], r)));
+        let category = rank_for(&self.ranks.group(&counts));
+
+        let flush = cards.iter().all(|&(_, suit)| suit == cards[0].1);
+        let ace = self.ranks.deck_size() - 1;
+        let high = match kickers[..] {
+            [a, _, _, _, e] if a - e == 4 => Some(a),
+            [a, 3, 2, 1, 0] if a == ace => Some(3),
+            _ => None,
+        };
+        match (high, flush) {
+            (Some(high), true) => (HandRank::StraightFlush, vec![high]),
+            _ if category >= HandRank::FullHouse => (category, kickers),
+            (_, true) => (HandRank::Flush, kickers),
+            (Some(high), false) => (HandRank::Straight, vec![high]),
+            _ => (category, kickers),
+        }
     }
-    (counts, value)
 }
 
-fn top_two(mut counts: [usize; 13]) -> (usize, usize) {
-    counts.sort_unstable();
-    let x1 = counts[counts.len() - 1];
-    let x2 = counts[counts.len() - 2];
-    (x1, x2)
+impl Scoring for Poker {
+    type Key = (HandRank, Vec<usize>);
+
+    fn key(&self, hand: &str) -> (HandRank, Vec<usize>) {
+        self.classify(&self.parse_hand(hand))
+    }
 }
 
-fn hand_rank(hand: &str) -> (HandRank, u64) {
-    let (counts, value) = card_counts(CARDS, hand);
-    (rank_for(top_two(counts)), value)
+pub fn solve_poker(input: &[(String, u64)]) -> u64 {
+    Poker::default().winnings(input)
 }
 
-fn hand_rank2(hand: &str) -> (HandRank, u64) {
-    let (mut counts, value) = card_counts(CARDS2, hand);
-    let j = counts[0];
-    counts[0] = 0;
-    let (x1, x2) = top_two(counts);
-    (rank_for((x1 + j, x2)), value)
+fn parse_bid(line: &str) -> (String, u64) {
+    let p = parser!(hand:string(alnum+) " "+ stake:u64 => (hand, stake));
+    p.parse(line).unwrap()
 }
 
 #[aoc_generator(day7)]
 pub fn input_generator(input: &str) -> Vec<(String, u64)> {
-    let p = parser!(lines(
-        hand:string(alnum+) " "+ stake:u64 => (hand, stake)
-    ));
-    p.parse(input).unwrap()
+    input.lines().map(parse_bid).collect()
 }
 
-fn solve(input: &Vec<(String, u64)>, score: fn(&str) -> (HandRank, u64)) -> u64 {
-    let mut work: Vec<((HandRank, u64), u64)> = input
-        .iter()
-        .map(|(hand, stake)| (score(hand), *stake))
-        .collect();
-    work.sort_by_key(|(rank, _)| *rank);
-
-    work.iter()
-        .enumerate()
-        .map(|(i, (_, stake))| stake * (1 + i as u64))
-        .sum()
+fn solve<S: Scoring>(input: &Vec<(String, u64)>, rules: S) -> u64 {
+    rules.winnings(input)
 }
 
 #[aoc(day7, part1)]
-fn solve_part1(input: &Vec<(String, u64)>) -> u64 {
-    solve(input, hand_rank)
+pub fn solve_part1(input: &Vec<(String, u64)>) -> u64 {
+    solve(input, Ruleset::camel())
 }
 
 #[aoc(day7, part2)]
-fn solve_part2(input: &Vec<(String, u64)>) -> u64 {
-    solve(input, hand_rank2)
+pub fn solve_part2(input: &Vec<(String, u64)>) -> u64 {
+    solve(input, Ruleset::jokers())
+}
+
+struct Fenwick(Vec<u64>);
+
+impl Fenwick {
+    fn add(&mut self, mut i: usize, x: u64) {
+        i += 1;
+        while i < self.0.len() {
+            self.0[i] += x;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    fn prefix(&self, mut i: usize) -> u64 {
+        let mut sum = 0;
+        while i > 0 {
+            sum += self.0[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+}
+
+// Largest table of hands `Winnings` will build
+const MAX_HANDS: usize = 1 << 20;
+
+// Keeps per-hand tallies over every possible hand, so memory does not grow with the input
+pub struct Winnings {
+    rules: Ruleset,
+    order: Vec<u32>,
+    counts: Fenwick,
+    stakes: Fenwick,
+    total: u64,
+}
+
+impl Winnings {
+    pub fn new(rules: Ruleset) -> Winnings {
+        let (n, len) = (rules.deck_size(), rules.hand_len() as u32);
+        let size = n
+            .checked_pow(len)
+            .filter(|&size| size <= MAX_HANDS)
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} cards in hands of {} is more than {} hands",
+                    n, len, MAX_HANDS
+                )
+            });
+        let mut hands: Vec<(HandKey, usize)> = (0..size)
+            .map(|v| {
+                let hand: String = (0..len)
+                    .rev()
+                    .map(|k| rules.deck[v / n.pow(k) % n])
+                    .collect();
+                (rules.hand_key(&hand), v)
+            })
+            .collect();
+        hands.sort_unstable();
+        let mut order = vec![0; size];
+        for (pos, &(_, v)) in hands.iter().enumerate() {
+            order[v] = pos as u32;
+        }
+        Winnings {
+            rules,
+            order,
+            counts: Fenwick(vec![0; size + 1]),
+            stakes: Fenwick(vec![0; size + 1]),
+            total: 0,
+        }
+    }
+
+    pub fn part1() -> Winnings {
+        Winnings::new(Ruleset::camel())
+    }
+
+    pub fn part2() -> Winnings {
+        Winnings::new(Ruleset::jokers())
+    }
+}
+
+impl Accumulator for Winnings {
+    type Record = (HandRank, u64);
+    type Total = u64;
+
+    // Yields the hand's category and its rank among the hands seen so far
+    fn feed(&mut self, line: &str) -> (HandRank, u64) {
+        let (hand, stake) = parse_bid(line);
+        let category = self.rules.category(&hand);
+        let pos = self.order[self.rules.value(&hand) as usize] as usize;
+        let rank = self.counts.prefix(pos + 1) + 1;
+        let overtaken = self.stakes.prefix(self.order.len()) - self.stakes.prefix(pos + 1);
+        let gained = checked::mul(7, "winnings", stake, rank);
+        self.total = checked::add(7, "winnings", self.total, gained);
+        self.total = checked::add(7, "winnings", self.total, overtaken);
+        self.counts.add(pos, 1);
+        self.stakes.add(pos, stake);
+        (category, rank)
+    }
+
+    fn total(&self) -> u64 {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{registry::fixture, stream};
+
+    #[test]
+    fn streaming() {
+        let input = fixture(7, "example") + "\nKK677 1";
+        let parsed = input_generator(&input);
+        let mut records = stream::lines(input.as_bytes(), Winnings::part1());
+        assert_eq!(
+            records.next().unwrap().unwrap(),
+            ((HandRank::OnePair, 1), 765)
+        );
+        assert_eq!(records.finish().unwrap(), solve_part1(&parsed));
+        let records = stream::lines(input.as_bytes(), Winnings::part2());
+        assert_eq!(records.finish().unwrap(), solve_part2(&parsed));
+    }
+
+    #[test]
+    fn rulesets() {
+        let camel = Ruleset::camel();
+        assert_eq!(camel.signature("T55J5"), vec![3, 1, 1]);
+        assert_eq!(Ruleset::jokers().signature("T55J5"), vec![4, 1]);
+        assert_eq!(Ruleset::jokers().signature("JJJJJ"), vec![5]);
+        assert_eq!(Ruleset::jokers().category("KTJJT"), HandRank::FourOfAKind);
+
+        // Two wild ranks and three-card hands
+        let small = Ruleset::new("ABCD", "CD", 3, TieBreak::Positional);
+        assert_eq!(small.signature("ACD"), vec![3]);
+        assert_eq!(small.signature("ABD"), vec![2, 1]);
+        let bids: Vec<(String, u64)> = ["ABD 1", "ACD 2", "BBA 3", "BAB 4"]
+            .iter()
+            .map(|line| parse_bid(line))
+            .collect();
+        let order: Vec<u64> = small.ranked(&bids).iter().map(|(_, s)| *s).collect();
+        assert_eq!(order, vec![3, 4, 1, 2]);
+        assert_eq!(small.winnings(&bids), 3 + 4 * 2 + 3 + 2 * 4);
+
+        // Six-card hands, with kickers compared group by group
+        let six = Ruleset::new(CARDS, "", 6, TieBreak::Grouped);
+        assert!(six.key("AAAKKK") < six.key("2222AK"));
+        assert!(six.key("22AAKQ") > six.key("AAKQJT"));
+        assert_eq!(six.key("KKQQQ2").tie, vec![10, 11, 0]);
+        assert_eq!(six.category("AAAAAA"), HandRank::FiveOfAKind);
+        assert_eq!(six.category("AAAKKK"), HandRank::FullHouse);
+        assert_eq!(six.category("AAAAKK"), HandRank::FourOfAKind);
+        assert_eq!(six.explain_hand("AAKKQQ").category, HandRank::TwoPair);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than")]
+    fn winnings_too_many_hands() {
+        Winnings::new(Ruleset::new(CARDS, "", 6, TieBreak::Positional));
+    }
+
+    #[test]
+    fn poker() {
+        let poker = Poker::default();
+        let category = |hand: &str| poker.key(hand).0;
+        assert_eq!(category("AhKhQhJhTh"), HandRank::StraightFlush);
+        assert_eq!(category("Ah2h3h4h5h"), HandRank::StraightFlush);
+        assert_eq!(category("9c9d9h9s2c"), HandRank::FourOfAKind);
+        assert_eq!(category("9c9d9h2s2c"), HandRank::FullHouse);
+        assert_eq!(category("Ah9h7h4h2h"), HandRank::Flush);
+        assert_eq!(category("As2d3c4h5h"), HandRank::Straight);
+        assert_eq!(category("QsKdAc2h3h"), HandRank::HighCard);
+        assert_eq!(category("9c9d9h4s2c"), HandRank::ThreeOfAKind);
+        assert!(poker.key("As2d3c4h5h") < poker.key("2s3d4c5h6h"));
+        assert!(poker.key("AsAdKc4h5h") > poker.key("AsAdQcJhTh"));
+        assert!(poker.key("3s3d2c2h4h") < poker.key("3s3d2c2hAh"));
+        assert!(poker.key("3s3d2c2hAh") < poker.key("4s4d2c2h3h"));
+
+        let bids = input_generator("AhKhQhJhTh 1\n2c2d5h7s9c 10\nAsAdKc4h5h 100");
+        assert_eq!(solve_poker(&bids), 10 + 100 * 2 + 3);
+    }
 
     #[test]
-    fn example() {
-        let input = r#"
-32T3K 765
-T55J5 684
-KK677 28
-KTJJT 220
-QQQJA 483"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 6440);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 5905);
+    fn every_poker_hand() {
+        let poker = Poker::default();
+        let deck: Vec<(usize, usize)> = (0..13).flat_map(|r| (0..4).map(move |s| (r, s))).collect();
+        let mut counts = [0usize; 10];
+        let mut hand = [(0, 0); 5];
+        for a in 0..52 {
+            hand[0] = deck[a];
+            for b in a + 1..52 {
+                hand[1] = deck[b];
+                for c in b + 1..52 {
+                    hand[2] = deck[c];
+                    for d in c + 1..52 {
+                        hand[3] = deck[d];
+                        for e in d + 1..52 {
+                            hand[4] = deck[e];
+                            counts[poker.classify(&hand).0 as usize] += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            counts,
+            [1302540, 1098240, 123552, 54912, 10200, 5108, 3744, 624, 40, 0]
+        );
+        assert_eq!(counts.iter().sum::<usize>(), 2598960);
+    }
+
+    #[test]
+    fn explanations() {
+        let jokers = Ruleset::jokers();
+        let e = jokers.explain_hand("KTJJT");
+        assert_eq!(e.category, HandRank::FourOfAKind);
+        assert_eq!(e.signature, vec![4, 1]);
+        assert_eq!(e.counts, vec![('T', 2), ('J', 2), ('K', 1)]);
+        assert_eq!(e.wilds, vec![('J', 2, 'T')]);
+        assert_eq!(jokers.decode(e.value), "KTJJT");
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "KTJJT FourOfAKind [4, 1] counts 2xT 2xJ 1xK 2xJ->T tie KTJJT ({})",
+                e.value
+            )
+        );
+        assert_eq!(jokers.explain_hand("JJJJJ").wilds, vec![('J', 5, 'A')]);
+        assert!(Ruleset::camel().explain_hand("KTJJT").wilds.is_empty());
+
+        let bids = input_generator(&fixture(7, "example"));
+        let table = jokers.explain(&bids);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[0].starts_with("    1 |    765 x 1     =      765 | 32T3K OnePair"));
+        assert!(lines[4].contains("KTJJT FourOfAKind"));
+        assert_eq!(lines[5], "total 5905");
     }
 }
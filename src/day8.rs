@@ -1,149 +1,729 @@
-use num_integer::Integer;
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fmt::Write,
+    hash::Hash,
+};
 
 use aoc_parse::{parser, prelude::*};
+use num_integer::Integer;
+use regex::Regex;
 
-const AAA: &str = "AAA";
-const ZZZ: &str = "ZZZ";
-
+// Each node may have any number of children, picked by the instruction letters
 pub struct Task {
     prog: String,
-    net: HashMap<String, (String, String)>,
+    net: HashMap<String, Vec<String>>,
 }
 
 #[aoc_generator(day8)]
 pub fn input_generator(input: &str) -> Task {
     let p = parser!(
-        prog:line(string(alpha+))
+        prog:line(string(alnum+))
         line("")
         nodes:lines(
             from:string(alnum+) " = ("
-            left:string(alnum+) ", "
-            right:string(alnum+) ")"
-            => (from, (left, right)))
-        => Task { prog, net: nodes.into_iter().collect::<HashMap<String, (String, String)>>() }
+            children:repeat_sep(string(alnum+), ", ") ")"
+            => (from, children))
+        => Task { prog, net: nodes.into_iter().collect::<HashMap<String, Vec<String>>>() }
     );
     p.parse(input).unwrap()
 }
 
+pub enum Predicate {
+    Exact(String),
+    Suffix(String),
+    Regex(Regex),
+    Callback(Box<dyn Fn(&str) -> bool>),
+}
+
+impl Predicate {
+    pub fn regex(pattern: &str) -> Result<Predicate, regex::Error> {
+        Regex::new(pattern).map(Predicate::Regex)
+    }
+
+    pub fn matches(&self, node: &str) -> bool {
+        match self {
+            Predicate::Exact(name) => node == name,
+            Predicate::Suffix(suffix) => node.ends_with(suffix.as_str()),
+            Predicate::Regex(re) => re.is_match(node),
+            Predicate::Callback(f) => f(node),
+        }
+    }
+}
+
+// The instruction letters, in the order of the children they pick
+pub struct Alphabet(Vec<char>);
+
+impl Alphabet {
+    pub fn new(letters: &str) -> Alphabet {
+        Alphabet(letters.chars().collect())
+    }
+
+    // None for children past the last letter, which no instruction can pick
+    pub fn letter(&self, child: usize) -> Option<char> {
+        self.0.get(child).copied()
+    }
+
+    pub fn child(&self, letter: char) -> usize {
+        self.0
+            .iter()
+            .position(|&l| l == letter)
+            .unwrap_or_else(|| panic!("{} is not an instruction", letter))
+    }
+}
+
+pub struct Rules {
+    pub start: Predicate,
+    pub end: Predicate,
+    pub alphabet: Alphabet,
+}
+
+impl Rules {
+    pub fn part1() -> Rules {
+        Rules {
+            start: Predicate::Exact("AAA".to_string()),
+            end: Predicate::Exact("ZZZ".to_string()),
+            alphabet: Alphabet::new("LR"),
+        }
+    }
+
+    pub fn part2() -> Rules {
+        Rules {
+            start: Predicate::Suffix("A".to_string()),
+            end: Predicate::Suffix("Z".to_string()),
+            alphabet: Alphabet::new("LR"),
+        }
+    }
+}
+
 #[aoc(day8, part1)]
-fn solve_part1(input: &Task) -> usize {
-    let prog: Vec<char> = input.prog.chars().into_iter().collect();
-    let mut n: usize = 0;
-    let aaa = AAA.to_string();
-    let mut node: &String = &aaa;
-    while node != ZZZ {
-        let cmd = prog[n % prog.len()];
-        n += 1;
-        let (left, right) = input.net.get(node).unwrap();
-        node = if cmd == 'L' { left } else { right };
-    }
-    n
-}
-
-fn hare_tortoise<T, F>(seed: T, step: F) -> usize
-where
-    T: Copy,
-    T: Eq,
-    F: Fn(T) -> T,
-{
-    let mut hare: T = seed;
-    let mut tortoise: T = seed;
-
-    loop {
-        hare = step(step(hare));
-        tortoise = step(tortoise);
-        if hare == tortoise {
-            let mut len: usize = 0;
-            loop {
-                hare = step(hare);
-                len += 1;
-                if hare == tortoise {
-                    break;
-                }
+pub fn solve_part1(input: &Task) -> u64 {
+    first_common(&cycles(input, &Rules::part1())).expect("there is no way from AAA to ZZZ")
+}
+
+// Where a walk ends up once it starts repeating: the first `tail` steps are never
+// revisited, and from then on everything repeats every `period` steps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub tail: u64,
+    pub period: u64,
+    // Steps before tail + period that land on an end node
+    pub hits: Vec<u64>,
+}
+
+impl Cycle {
+    pub fn find<S, F, E>(seed: S, step: F, is_end: E) -> Cycle
+    where
+        S: Copy + Eq + Hash,
+        F: Fn(S) -> S,
+        E: Fn(S) -> bool,
+    {
+        let mut seen: HashMap<S, u64> = HashMap::new();
+        let mut hits = Vec::new();
+        let mut state = seed;
+        let mut n = 0;
+        while let Entry::Vacant(slot) = seen.entry(state) {
+            slot.insert(n);
+            if is_end(state) {
+                hits.push(n);
             }
+            state = step(state);
+            n += 1;
+        }
+        let tail = seen[&state];
+        Cycle {
+            tail,
+            period: n - tail,
+            hits,
+        }
+    }
 
-            return len;
+    pub fn hits_at(&self, t: u64) -> bool {
+        if t < self.tail {
+            return self.hits.binary_search(&t).is_ok();
         }
+        let r = self.tail + (t - self.tail) % self.period;
+        self.hits.binary_search(&r).is_ok()
+    }
+
+    // Hits that recur, as residues modulo the period
+    fn residues(&self) -> impl Iterator<Item = u64> + '_ {
+        self.hits
+            .iter()
+            .filter(|&&t| t >= self.tail)
+            .map(|&t| t % self.period)
     }
 }
 
-#[aoc(day8, part2)]
-fn solve_part2(input: &Task) -> i64 {
-    let prog: Vec<char> = input.prog.chars().into_iter().collect();
-    let prog_len = prog.len();
-    let starts: Vec<&String> = input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoSolution {
+    // The rules pick out no start nodes, so there is no walk to finish
+    NoStarts,
+    // This start never reaches an end node at all
+    NeverEnds { start: String },
+    // No step is shared by this start and all the ones before it
+    Incompatible { start: String },
+    // Every walk does end together, but not until a step beyond u64
+    TooLarge,
+}
+
+// x = a1 (mod m1) and x = a2 (mod m2), for any moduli; None when the two never agree.
+// The arithmetic is always checked, since the combined modulus easily outgrows i128.
+pub fn crt(a1: i128, m1: i128, a2: i128, m2: i128) -> Result<Option<(i128, i128)>, NoSolution> {
+    let g = m1.extended_gcd(&m2);
+    if (a2 - a1) % g.gcd != 0 {
+        return Ok(None);
+    }
+    let l = (m1 / g.gcd).checked_mul(m2).ok_or(NoSolution::TooLarge)?;
+    let k = ((a2 - a1) / g.gcd % (m2 / g.gcd))
+        .checked_mul(g.x)
+        .ok_or(NoSolution::TooLarge)?
+        .rem_euclid(m2 / g.gcd);
+    let x = m1
+        .checked_mul(k)
+        .and_then(|x| x.checked_add(a1))
+        .ok_or(NoSolution::TooLarge)?;
+    Ok(Some((x.rem_euclid(l), l)))
+}
+
+// The first step at which every walk is on an end node at once
+pub fn first_common(cycles: &[(String, Cycle)]) -> Result<u64, NoSolution> {
+    for (start, cycle) in cycles {
+        if cycle.hits.is_empty() {
+            return Err(NoSolution::NeverEnds {
+                start: start.clone(),
+            });
+        }
+    }
+    let Some((_, first)) = cycles.first() else {
+        return Err(NoSolution::NoStarts);
+    };
+    // Before every walk has reached its loop, just try the first walk's hits
+    let settled = cycles.iter().map(|(_, c)| c.tail).max().unwrap();
+    if let Some(t) = (0..settled)
+        .filter(|&t| first.hits_at(t))
+        .find(|&t| cycles.iter().all(|(_, c)| c.hits_at(t)))
+    {
+        return Ok(t);
+    }
+
+    let mut combined: Vec<i128> = vec![0];
+    let mut modulus: i128 = 1;
+    for (start, cycle) in cycles {
+        let period = cycle.period as i128;
+        let mut next: Vec<i128> = Vec::new();
+        let mut next_modulus = modulus;
+        for &a in combined.iter() {
+            for r in cycle.residues() {
+                if let Some((x, l)) = crt(a, modulus, r as i128, period)? {
+                    next.push(x);
+                    next_modulus = l;
+                }
+            }
+        }
+        next.sort_unstable();
+        next.dedup();
+        if next.is_empty() {
+            return Err(NoSolution::Incompatible {
+                start: start.clone(),
+            });
+        }
+        combined = next;
+        modulus = next_modulus;
+    }
+    let settled = settled as i128;
+    // The first time each solution comes round at or after the point every walk is looping
+    let first = combined.into_iter().try_fold(i128::MAX, |first, x| {
+        let periods = if x >= settled {
+            0
+        } else {
+            (settled - x - 1) / modulus + 1
+        };
+        periods
+            .checked_mul(modulus)
+            .and_then(|step| step.checked_add(x))
+            .map(|step| first.min(step))
+            .ok_or(NoSolution::TooLarge)
+    })?;
+    u64::try_from(first).map_err(|_| NoSolution::TooLarge)
+}
+
+// One walk per start node, in name order
+pub fn cycles(input: &Task, rules: &Rules) -> Vec<(String, Cycle)> {
+    let prog: Vec<usize> = input
+        .prog
+        .chars()
+        .map(|c| rules.alphabet.child(c))
+        .collect();
+    let mut starts: Vec<&String> = input
         .net
         .keys()
-        .into_iter()
-        .filter(|&node| node.ends_with("A"))
+        .filter(|&node| rules.start.matches(node))
         .collect();
-
+    starts.sort();
     starts
-        .iter()
-        .map(|&s| {
-            // It just so happened that in this task the first occurence of the flag
-            // is exactly the same value as the length of the loop in the instructions
-            // So we can drop the tortoise/hare altogether, and simply find the first index,
-            // but at this point it would be completely stupid
-            hare_tortoise((s, 0), |(s, i)| {
-                let cmd = prog[i];
-                let (left, right) = input.net.get(s).unwrap();
-                (if cmd == 'L' { left } else { right }, (i + 1) % prog_len)
-            })
+        .into_iter()
+        .map(|s| {
+            // A missing child, or a node with no entry at all, keeps the walk where it is
+            let step = |(node, i)| {
+                let next = input
+                    .net
+                    .get(node)
+                    .and_then(|children| children.get(prog[i]))
+                    .map_or(node, String::as_str);
+                (next, (i + 1) % prog.len())
+            };
+            let seed: (&str, usize) = (s.as_str(), 0);
+            let cycle = Cycle::find(seed, step, |(node, _)| rules.end.matches(node));
+            (s.clone(), cycle)
         })
-        .fold(1, |acc, cycle_len| acc.gcd_lcm(&(cycle_len as i64)).1)
+        .collect()
+}
+
+#[aoc(day8, part2)]
+pub fn solve_part2(input: &Task) -> u64 {
+    first_common(&cycles(input, &Rules::part2()))
+        .expect("the ghosts never all stand on an end node")
+}
+
+// The network with nodes numbered in name order; children follow the instruction letters
+pub struct Network<'a> {
+    names: Vec<String>,
+    children: Vec<Vec<usize>>,
+    starts: Vec<bool>,
+    ends: Vec<bool>,
+    alphabet: &'a Alphabet,
+}
+
+// A node's state at a point in the instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct State {
+    pub node: usize,
+    pub at: usize,
+}
+
+// Every state reachable from a start, each with the one state it steps to
+pub struct StateGraph {
+    pub states: Vec<State>,
+    pub next: Vec<usize>,
+}
+
+impl<'a> Network<'a> {
+    pub fn new(task: &Task, rules: &'a Rules) -> Network<'a> {
+        let mut names: Vec<String> = task
+            .net
+            .iter()
+            .flat_map(|(from, children)| children.iter().chain([from]))
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        let index: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        // Nodes that are only ever mentioned as children have no way out
+        let children = names
+            .iter()
+            .map(|name| match task.net.get(name) {
+                Some(children) => children.iter().map(|c| index[c.as_str()]).collect(),
+                None => Vec::new(),
+            })
+            .collect();
+        Network {
+            starts: names.iter().map(|name| rules.start.matches(name)).collect(),
+            ends: names.iter().map(|name| rules.end.matches(name)).collect(),
+            names,
+            children,
+            alphabet: &rules.alphabet,
+        }
+    }
+
+    pub fn name(&self, node: usize) -> &str {
+        &self.names[node]
+    }
+
+    fn names_of(&self, nodes: impl Iterator<Item = usize>) -> Vec<&str> {
+        nodes.map(|n| self.name(n)).collect()
+    }
+
+    // Kosaraju's algorithm, iteratively; components come out in name order of their first node
+    pub fn components(&self) -> Vec<Vec<&str>> {
+        let n = self.names.len();
+        let mut order = Vec::with_capacity(n);
+        let mut visited = vec![false; n];
+        for root in 0..n {
+            if visited[root] {
+                continue;
+            }
+            visited[root] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((node, child)) = stack.pop() {
+                match self.children[node].get(child) {
+                    Some(&next) => {
+                        stack.push((node, child + 1));
+                        if !visited[next] {
+                            visited[next] = true;
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => order.push(node),
+                }
+            }
+        }
+
+        let mut parents = vec![Vec::new(); n];
+        for (node, children) in self.children.iter().enumerate() {
+            for &child in children {
+                parents[child].push(node);
+            }
+        }
+        let mut component = vec![usize::MAX; n];
+        let mut components: Vec<Vec<usize>> = Vec::new();
+        for &root in order.iter().rev() {
+            if component[root] != usize::MAX {
+                continue;
+            }
+            let id = components.len();
+            let mut members = vec![root];
+            component[root] = id;
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                for &parent in parents[node].iter() {
+                    if component[parent] == usize::MAX {
+                        component[parent] = id;
+                        members.push(parent);
+                        stack.push(parent);
+                    }
+                }
+            }
+            members.sort_unstable();
+            components.push(members);
+        }
+        components.sort_unstable();
+        components
+            .into_iter()
+            .map(|c| self.names_of(c.into_iter()))
+            .collect()
+    }
+
+    fn reachable(&self) -> Vec<bool> {
+        let mut seen = self.starts.clone();
+        let mut stack: Vec<usize> = (0..self.names.len()).filter(|&n| seen[n]).collect();
+        while let Some(node) = stack.pop() {
+            for &child in self.children[node].iter() {
+                if !seen[child] {
+                    seen[child] = true;
+                    stack.push(child);
+                }
+            }
+        }
+        seen
+    }
+
+    pub fn unreachable(&self) -> Vec<&str> {
+        let reachable = self.reachable();
+        self.names_of((0..self.names.len()).filter(|&n| !reachable[n]))
+    }
+
+    // Nodes that can never be left: every way out leads straight back, or there is none
+    pub fn sinks(&self) -> Vec<&str> {
+        self.names_of(
+            (0..self.names.len()).filter(|&n| self.children[n].iter().all(|&child| child == n)),
+        )
+    }
+
+    pub fn state_graph(&self, prog: &str) -> StateGraph {
+        let prog: Vec<usize> = prog.chars().map(|c| self.alphabet.child(c)).collect();
+        let mut index: HashMap<State, usize> = HashMap::new();
+        let mut states = Vec::new();
+        let mut pending: Vec<State> = (0..self.names.len())
+            .filter(|&n| self.starts[n])
+            .map(|node| State { node, at: 0 })
+            .collect();
+        let mut edges = Vec::new();
+        while let Some(state) = pending.pop() {
+            if index.contains_key(&state) {
+                continue;
+            }
+            index.insert(state, states.len());
+            states.push(state);
+            // A missing child keeps the walk where it is
+            let node = self.children[state.node]
+                .get(prog[state.at])
+                .copied()
+                .unwrap_or(state.node);
+            let next = State {
+                node,
+                at: (state.at + 1) % prog.len(),
+            };
+            edges.push(next);
+            pending.push(next);
+        }
+        let next = edges.iter().map(|s| index[s]).collect();
+        StateGraph { states, next }
+    }
+
+    fn style(&self, node: usize) -> &'static str {
+        match (self.starts[node], self.ends[node]) {
+            (true, true) => " [style=filled, fillcolor=gold]",
+            (true, false) => " [style=filled, fillcolor=palegreen]",
+            (false, true) => " [style=filled, fillcolor=salmon]",
+            (false, false) => "",
+        }
+    }
+
+    // Starts are green, ends red, and nodes that are both gold
+    pub fn dot(&self) -> String {
+        let mut out = String::from("digraph network {\n");
+        for (node, name) in self.names.iter().enumerate() {
+            writeln!(out, "  \"{}\"{};", name, self.style(node)).unwrap();
+        }
+        for (node, children) in self.children.iter().enumerate() {
+            for (k, &child) in children.iter().enumerate() {
+                // Children no letter picks are labelled by their position instead
+                let label = match self.alphabet.letter(k) {
+                    Some(letter) => letter.to_string(),
+                    None => k.to_string(),
+                };
+                writeln!(
+                    out,
+                    "  \"{}\" -> \"{}\" [label=\"{}\"];",
+                    self.name(node),
+                    self.name(child),
+                    label
+                )
+                .unwrap();
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn state_dot(&self, graph: &StateGraph) -> String {
+        let id = |s: &State| format!("\"{}:{}\"", self.name(s.node), s.at);
+        let mut out = String::from("digraph states {\n");
+        let mut order: Vec<usize> = (0..graph.states.len()).collect();
+        order.sort_by_key(|&i| (self.name(graph.states[i].node), graph.states[i].at));
+        for &i in order.iter() {
+            let state = &graph.states[i];
+            let style = if state.at == 0 || self.ends[state.node] {
+                self.style(state.node)
+            } else {
+                ""
+            };
+            writeln!(out, "  {}{};", id(state), style).unwrap();
+        }
+        for &i in order.iter() {
+            let next = &graph.states[graph.next[i]];
+            writeln!(out, "  {} -> {};", id(&graph.states[i]), id(next)).unwrap();
+        }
+        out.push_str("}\n");
+        out
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::fixture;
 
     #[test]
-    fn example1() {
-        let input = r#"RL
-
-AAA = (BBB, CCC)
-BBB = (DDD, EEE)
-CCC = (ZZZ, GGG)
-DDD = (DDD, DDD)
-EEE = (EEE, EEE)
-GGG = (GGG, GGG)
-ZZZ = (ZZZ, ZZZ)"#
-            .trim();
+    fn general_cycles() {
+        let input = r#"L
+
+11A = (11Z, 11Z)
+11Z = (11A, 11A)
+22A = (22B, 22B)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22A, 22A)
+33A = (33B, 33B)
+33B = (33C, 33C)
+33C = (33Z, 33Z)
+33Z = (33D, 33D)
+33D = (3EZ, 3EZ)
+3EZ = (33C, 33C)"#;
         let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 2);
+        let cycles = cycles(&parsed, &Rules::part2());
+        assert_eq!(
+            cycles[2].1,
+            Cycle {
+                tail: 2,
+                period: 4,
+                hits: vec![3, 5]
+            }
+        );
+        assert_eq!(first_common(&cycles), Ok(3));
+        assert_eq!(first_common(&cycles[..1]), Ok(1));
+        assert_eq!(first_common(&[]), Err(NoSolution::NoStarts));
+        assert_eq!(crt(1, 2, 3, 4), Ok(Some((3, 4))));
+        assert_eq!(crt(2, 6, 5, 9), Ok(Some((14, 18))));
+        assert_eq!(crt(0, 2, 1, 4), Ok(None));
+        let big = i128::MAX / 3;
+        assert_eq!(crt(0, big, 1, big - 1), Err(NoSolution::TooLarge));
     }
 
     #[test]
-    fn example2() {
-        let input = r#"LLR
+    fn no_common_step() {
+        let input = r#"L
 
-AAA = (BBB, BBB)
-BBB = (AAA, ZZZ)
-ZZZ = (ZZZ, ZZZ)"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 6);
+11A = (11Z, 11Z)
+11Z = (11A, 11A)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22C, 22C)
+22C = (22A, 22A)
+44A = (44Z, 44Z)
+44Z = (44B, 44B)
+44B = (44C, 44C)
+44C = (44B, 44B)"#;
+        let cycles = cycles(&input_generator(input), &Rules::part2());
+        assert_eq!(
+            first_common(&cycles[..2]),
+            Err(NoSolution::Incompatible {
+                start: "22A".to_string()
+            })
+        );
+        // 44A only ever ends at step 1, before its loop, where 11A is fine too
+        assert_eq!(first_common(&[cycles[0].clone(), cycles[2].clone()]), Ok(1));
+        assert_eq!(
+            first_common(&cycles[1..]),
+            Err(NoSolution::Incompatible {
+                start: "44A".to_string()
+            })
+        );
+        let stuck = Cycle::find(0u8, |_| 0, |_| false);
+        assert_eq!(
+            first_common(&[("00A".to_string(), stuck)]),
+            Err(NoSolution::NeverEnds {
+                start: "00A".to_string()
+            })
+        );
+        // Coprime periods whose product is past u64, each ending once per loop
+        let ends_at = |hit: u64, period: u64| Cycle {
+            tail: 0,
+            period,
+            hits: vec![hit],
+        };
+        let (p, q) = (1 << 40, (1 << 40) - 1);
+        assert_eq!(
+            first_common(&[
+                ("11A".to_string(), ends_at(1, p)),
+                ("22A".to_string(), ends_at(0, q)),
+            ]),
+            Err(NoSolution::TooLarge)
+        );
+        // Here even the combined period is past i128
+        let (p, q) = (u64::MAX, u64::MAX - 1);
+        assert_eq!(
+            first_common(&[
+                ("11A".to_string(), ends_at(1, p)),
+                ("22A".to_string(), ends_at(0, q)),
+            ]),
+            Err(NoSolution::TooLarge)
+        );
     }
 
     #[test]
-    fn example_pt2() {
-        let input = r#"LR
+    fn network_analysis() {
+        let input = fixture(8, "ghosts") + "\nYYY = (XXX, QQQ)";
+        let rules = Rules::part2();
+        let network = Network::new(&input_generator(&input), &rules);
+        assert_eq!(
+            network.components(),
+            vec![
+                vec!["11A"],
+                vec!["11B", "11Z"],
+                vec!["22A"],
+                vec!["22B", "22C", "22Z"],
+                vec!["QQQ"],
+                vec!["XXX"],
+                vec!["YYY"],
+            ]
+        );
+        assert_eq!(network.unreachable(), vec!["QQQ", "YYY"]);
+        assert_eq!(network.sinks(), vec!["QQQ", "XXX"]);
 
-11A = (11B, XXX)
-11B = (XXX, 11Z)
-11Z = (11B, XXX)
-22A = (22B, XXX)
-22B = (22C, 22C)
-22C = (22Z, 22Z)
-22Z = (22B, 22B)
-XXX = (XXX, XXX)"#
-            .trim();
-        let parsed = input_generator(input);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 6);
+        let graph = network.state_graph("LR");
+        assert_eq!(graph.states.len(), graph.next.len());
+        let start = graph
+            .states
+            .iter()
+            .position(|s| network.name(s.node) == "11A")
+            .unwrap();
+        let next = graph.states[graph.next[start]];
+        assert_eq!((network.name(next.node), next.at), ("11B", 1));
+        // 11A:0, 11B:1, 11Z:0, then 11B:1 again; the 22 walk passes seven states
+        assert_eq!(graph.states.len(), 3 + 7);
+
+        let dot = network.dot();
+        assert!(dot.starts_with("digraph network {\n"));
+        assert!(dot.contains("  \"11A\" [style=filled, fillcolor=palegreen];\n"));
+        assert!(dot.contains("  \"11Z\" [style=filled, fillcolor=salmon];\n"));
+        assert!(dot.contains("  \"11B\" -> \"11Z\" [label=\"R\"];\n"));
+        let states = network.state_dot(&graph);
+        assert!(states.contains("  \"11A:0\" -> \"11B:1\";\n"));
+        assert!(states.contains("  \"22Z:1\" [style=filled, fillcolor=salmon];\n"));
+    }
+
+    #[test]
+    fn general_networks() {
+        let input = r#"0210
+
+AAA = (BBB, CCC, DDD)
+BBB = (AAA, AAA, FIN)
+CCC = (BBB, DDD, AAA)
+DDD = (DDD, DDD, DDD)
+FIN = (FIN, FIN, FIN)"#;
+        let task = input_generator(input);
+        let rules = Rules {
+            start: Predicate::regex("^A+$").unwrap(),
+            end: Predicate::Callback(Box::new(|node| node == "FIN")),
+            alphabet: Alphabet::new("012"),
+        };
+        // AAA -0-> BBB -2-> FIN
+        assert_eq!(first_common(&cycles(&task, &rules)), Ok(2));
+        let rules = Rules {
+            end: Predicate::Exact("DDD".to_string()),
+            ..rules
+        };
+        // AAA -0-> BBB -2-> FIN, which never leads to DDD
+        assert_eq!(
+            first_common(&cycles(&task, &rules)),
+            Err(NoSolution::NeverEnds {
+                start: "AAA".to_string()
+            })
+        );
+        let network = Network::new(&task, &rules);
+        assert!(network
+            .dot()
+            .contains("  \"CCC\" -> \"AAA\" [label=\"2\"];\n"));
+        assert_eq!(network.sinks(), vec!["DDD", "FIN"]);
+
+        let lr = input_generator("LR\n\nAAA = (ZZZ, AAA)\nZZZ = (ZZZ, ZZZ)");
+        assert_eq!(solve_part1(&lr), 1);
+
+        // Walks and the state graph both stay put on a missing child
+        let short = input_generator("RL\n\nAAA = (AAA, BBB)\nBBB = (ZZZ)");
+        assert_eq!(solve_part1(&short), 2);
+        let rules = Rules::part1();
+        let network = Network::new(&short, &rules);
+        let graph = network.state_graph("RL");
+        let walk: Vec<(&str, usize)> = graph
+            .states
+            .iter()
+            .map(|s| (network.name(s.node), s.at))
+            .collect();
+        assert_eq!(walk, vec![("AAA", 0), ("BBB", 1), ("ZZZ", 0), ("ZZZ", 1)]);
+        assert_eq!(graph.next[3], 2);
+
+        // More children than letters: the extra edge is labelled by its position
+        let wide = input_generator("L\n\nAAA = (BBB, ZZZ, CCC)");
+        let dot = Network::new(&wide, &rules).dot();
+        assert!(dot.contains("  \"AAA\" -> \"ZZZ\" [label=\"R\"];\n"));
+        assert!(dot.contains("  \"AAA\" -> \"CCC\" [label=\"2\"];\n"));
+        assert!(Predicate::Suffix("Z".to_string()).matches("11Z"));
+        assert!(Predicate::regex("(").is_err());
     }
 }
@@ -1,56 +1,94 @@
+use crate::{checked, poly::Sequence, stream::Accumulator};
 use aoc_parse::{parser, prelude::*};
 
 pub struct Task {
-    seqs: Vec<Vec<i32>>,
+    seqs: Vec<Vec<i128>>,
+}
+
+fn parse_seq(line: &str) -> Vec<i128> {
+    let p = parser!(repeat_sep(i128, " "));
+    p.parse(line).unwrap()
 }
 
 #[aoc_generator(day9)]
 pub fn input_generator(input: &str) -> Task {
-    let p = parser!(seqs:lines(repeat_sep(i32, " ")) => Task { seqs });
-    p.parse(input).unwrap()
+    Task {
+        seqs: input.lines().map(parse_seq).collect(),
+    }
 }
 
-fn extrapolate(xs: &Vec<i32>) -> i32 {
-    if xs.iter().all(|&x| x == 0) {
-        0
-    } else {
-        let row: Vec<i32> = xs.windows(2).map(|pair| pair[1] - pair[0]).collect();
-        let prev = extrapolate(&row);
-        xs[xs.len() - 1] + prev
-    }
+fn extrapolate(xs: &[i128], at: i64) -> i128 {
+    let term = Sequence::new(xs.to_vec()).and_then(|seq| seq.at(&i128::from(at)));
+    checked::expect(9, "extrapolation", term.ok())
 }
 
 #[aoc(day9, part1)]
-fn solve_part1(input: &Task) -> i32 {
-    input.seqs.iter().map(extrapolate).sum()
+pub fn solve_part1(input: &Task) -> i128 {
+    checked::sum(
+        9,
+        "sum of extrapolations",
+        input.seqs.iter().map(|xs| extrapolate(xs, xs.len() as i64)),
+    )
 }
 
 #[aoc(day9, part2)]
-fn solve_part2(input: &Task) -> i32 {
-    let reversed = Task {
-        seqs: input
-            .seqs
-            .iter()
-            .map(|xs| xs.iter().copied().rev().collect())
-            .collect(),
-    };
-    solve_part1(&reversed)
+pub fn solve_part2(input: &Task) -> i128 {
+    checked::sum(
+        9,
+        "sum of extrapolations",
+        input.seqs.iter().map(|xs| extrapolate(xs, -1)),
+    )
+}
+
+pub struct Extrapolation {
+    backwards: bool,
+    total: i128,
+}
+
+impl Extrapolation {
+    pub fn part1() -> Extrapolation {
+        Extrapolation {
+            backwards: false,
+            total: 0,
+        }
+    }
+
+    pub fn part2() -> Extrapolation {
+        Extrapolation {
+            backwards: true,
+            total: 0,
+        }
+    }
+}
+
+impl Accumulator for Extrapolation {
+    type Record = i128;
+    type Total = i128;
+
+    fn feed(&mut self, line: &str) -> i128 {
+        let xs = parse_seq(line);
+        let at = if self.backwards { -1 } else { xs.len() as i64 };
+        let x = extrapolate(&xs, at);
+        self.total = checked::add(9, "sum of extrapolations", self.total, x);
+        x
+    }
+
+    fn total(&self) -> i128 {
+        self.total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{registry::fixture, stream};
 
     #[test]
-    fn example1() {
-        let input = r#"0 3 6 9 12 15
-1 3 6 10 15 21
-10 13 16 21 30 45"#
-            .trim();
-        let parsed = input_generator(input);
-        let result1 = solve_part1(&parsed);
-        assert_eq!(result1, 114);
-        let result2 = solve_part2(&parsed);
-        assert_eq!(result2, 2);
+    fn streaming() {
+        let input = fixture(9, "example");
+        let records: Vec<(i128, i128)> = stream::lines(input.as_bytes(), Extrapolation::part2())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(records, vec![(-3, -3), (0, -3), (5, 2)]);
     }
 }
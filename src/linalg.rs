@@ -0,0 +1,201 @@
+use std::ops::RangeInclusive;
+
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+
+pub fn isqrt<T>(n: &T) -> T
+where
+    T: Integer + Clone,
+{
+    let two = T::one() + T::one();
+    if *n < two {
+        return n.clone();
+    }
+    let mut x = n.clone();
+    let step = |x: &T| {
+        let q = n.clone() / x.clone();
+        let (lo, hi) = if q < *x {
+            (q, x.clone())
+        } else {
+            (x.clone(), q)
+        };
+        lo.clone() + (hi - lo) / two.clone()
+    };
+    let mut y = step(&x);
+    while y < x {
+        x = y;
+        y = step(&x);
+    }
+    x
+}
+
+// The squares in a quadratic only stay exact one size up
+pub trait Wide: Integer + Clone {
+    type Wider: Integer + Clone;
+
+    fn widen(&self) -> Self::Wider;
+    fn narrow(wide: Self::Wider) -> Self;
+}
+
+impl Wide for u64 {
+    type Wider = u128;
+
+    fn widen(&self) -> u128 {
+        *self as u128
+    }
+
+    fn narrow(wide: u128) -> u64 {
+        wide as u64
+    }
+}
+
+impl Wide for u128 {
+    type Wider = BigUint;
+
+    fn widen(&self) -> BigUint {
+        BigUint::from(*self)
+    }
+
+    fn narrow(wide: BigUint) -> u128 {
+        wide.to_u128().unwrap()
+    }
+}
+
+impl Wide for BigUint {
+    type Wider = BigUint;
+
+    fn widen(&self) -> BigUint {
+        self.clone()
+    }
+
+    fn narrow(wide: BigUint) -> BigUint {
+        wide
+    }
+}
+
+// Integers x with x * (b - x) > c, i.e. strictly between the roots of x^2 - bx + c.
+// Squares are taken in `T::Wider`; the bounds never exceed b, so they fit back in T.
+pub fn quadratic_interior<T: Wide>(b: &T, c: &T) -> Option<RangeInclusive<T>> {
+    let (b, c) = (b.widen(), c.widen());
+    let two = T::Wider::one() + T::Wider::one();
+    let four = two.clone() + two.clone();
+    let b_sq = b.clone() * b.clone();
+    let four_c = four * c.clone();
+    if b_sq <= four_c {
+        return None;
+    }
+    let s = isqrt(&(b_sq - four_c));
+    let wins = |x: &T::Wider| x.clone() * (b.clone() - x.clone()) > c;
+    let half = b.clone() / two.clone();
+
+    let mut lo = (b.clone() - s) / two;
+    while !wins(&lo) {
+        if lo >= half {
+            return None;
+        }
+        lo = lo + T::Wider::one();
+    }
+    while !lo.is_zero() && wins(&(lo.clone() - T::Wider::one())) {
+        lo = lo - T::Wider::one();
+    }
+    let hi = b - lo.clone();
+    Some(T::narrow(lo)..=T::narrow(hi))
+}
+
+pub fn solve(a: &[Vec<i128>], b: &[i128]) -> Option<Vec<BigRational>> {
+    let n = b.len();
+    let mut m: Vec<Vec<BigRational>> = a
+        .iter()
+        .zip(b)
+        .map(|(row, &rhs)| {
+            row.iter()
+                .chain([rhs].iter())
+                .map(|&x| BigRational::from_integer(BigInt::from(x)))
+                .collect()
+        })
+        .collect();
+
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| !m[r][col].is_zero())?;
+        m.swap(col, pivot);
+        let pivot_row = m[col].clone();
+        for (r, row) in m.iter_mut().enumerate() {
+            if r == col || row[col].is_zero() {
+                continue;
+            }
+            let factor = &row[col] / &pivot_row[col];
+            for (x, p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                *x -= &factor * p;
+            }
+        }
+    }
+
+    Some((0..n).map(|i| &m[i][n] / &m[i][i]).collect())
+}
+
+pub fn solve_integer(a: &[Vec<i128>], b: &[i128]) -> Option<Vec<i128>> {
+    solve(a, b)?
+        .into_iter()
+        .map(|x| {
+            if x.is_integer() {
+                x.to_integer().to_i128()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_roots() {
+        assert_eq!(isqrt(&0u64), 0);
+        assert_eq!(isqrt(&1u64), 1);
+        assert_eq!(isqrt(&15u64), 3);
+        assert_eq!(isqrt(&16u64), 4);
+        assert_eq!(isqrt(&u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt(&u128::MAX), u64::MAX as u128);
+        let big: BigUint = "100000000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        let root: BigUint = "316227766016837933199".parse().unwrap();
+        assert_eq!(isqrt(&big), root);
+    }
+
+    #[test]
+    fn interior() {
+        assert_eq!(quadratic_interior(&7u64, &9), Some(2..=5));
+        assert_eq!(quadratic_interior(&30u64, &200), Some(11..=19));
+        assert_eq!(quadratic_interior(&4u64, &4), None);
+        assert_eq!(quadratic_interior(&4u64, &3), Some(2..=2));
+        assert_eq!(quadratic_interior(&1u64, &0), None);
+        // b * b is past u64, which the bounds are not
+        assert_eq!(
+            quadratic_interior(&(1u64 << 40), &0),
+            Some(1..=(1 << 40) - 1)
+        );
+        assert_eq!(
+            quadratic_interior(&u64::MAX, &(u64::MAX / 4)),
+            quadratic_interior(&(u64::MAX as u128), &(u64::MAX as u128 / 4))
+                .map(|r| *r.start() as u64..=*r.end() as u64)
+        );
+    }
+
+    #[test]
+    fn linear_system() {
+        let a = vec![vec![0, 2, 1], vec![1, 1, 1], vec![2, 0, 3]];
+        let b = vec![7, 6, 11];
+        assert_eq!(solve_integer(&a, &b), Some(vec![1, 2, 3]));
+
+        let half = solve(&[vec![2]], &[1]).unwrap();
+        assert_eq!(half[0], BigRational::new(1.into(), 2.into()));
+        assert_eq!(solve_integer(&[vec![2]], &[1]), None);
+
+        assert_eq!(solve(&[vec![1, 2], vec![2, 4]], &[1, 2]), None);
+    }
+}
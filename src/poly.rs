@@ -0,0 +1,195 @@
+use num_integer::Integer;
+use num_rational::Ratio;
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub};
+
+// The terms, replaced in place by their leading differences: x0, Δx0, Δ²x0, ...
+// which are the coefficients of the interpolating polynomial in Newton form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence<T> {
+    newton: Vec<T>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolyError {
+    // The differences never vanish within the terms given
+    NotPolynomial,
+    // A difference, coefficient or term does not fit in the integer type
+    Overflow,
+}
+
+fn or_overflow<T>(x: Option<T>) -> Result<T, PolyError> {
+    x.ok_or(PolyError::Overflow)
+}
+
+impl<T> Sequence<T>
+where
+    T: Integer + Clone + CheckedAdd + CheckedSub + CheckedMul,
+{
+    // The unique polynomial of degree below xs.len() through all the terms
+    pub fn new(mut xs: Vec<T>) -> Result<Sequence<T>, PolyError> {
+        for level in 1..xs.len() {
+            for i in (level..xs.len()).rev() {
+                xs[i] = or_overflow(xs[i].checked_sub(&xs[i - 1]))?;
+            }
+        }
+        while xs.last().is_some_and(|x| x.is_zero()) {
+            xs.pop();
+        }
+        Ok(Sequence { newton: xs })
+    }
+
+    // Only accepts sequences where the vanishing differences are witnessed by at least one extra term
+    pub fn fit(xs: Vec<T>) -> Result<Sequence<T>, PolyError> {
+        let len = xs.len();
+        let seq = Sequence::new(xs)?;
+        if seq.newton.len() < len {
+            Ok(seq)
+        } else {
+            Err(PolyError::NotPolynomial)
+        }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.newton.len().saturating_sub(1)
+    }
+
+    pub fn newton(&self) -> &[T] {
+        &self.newton
+    }
+
+    // Coefficients of x^0, x^1, ... from the Newton form, expanding each C(x, k)
+    pub fn coefficients(&self) -> Result<Vec<Ratio<T>>, PolyError> {
+        let mut result = vec![Ratio::from_integer(T::zero()); self.newton.len()];
+        // x (x - 1) ... (x - k + 1), lowest power first, and k!
+        let mut falling: Vec<T> = vec![T::one()];
+        let mut factorial = T::one();
+        let mut k = T::zero();
+        for (i, d) in self.newton.iter().enumerate() {
+            if i > 0 {
+                let shift = k.clone();
+                k = k + T::one();
+                factorial = or_overflow(factorial.checked_mul(&k))?;
+                let mut next = vec![T::zero(); falling.len() + 1];
+                for (j, c) in falling.iter().enumerate() {
+                    next[j + 1] = or_overflow(next[j + 1].checked_add(c))?;
+                    let shifted = or_overflow(c.checked_mul(&shift))?;
+                    next[j] = or_overflow(next[j].checked_sub(&shifted))?;
+                }
+                falling = next;
+            }
+            for (j, c) in falling.iter().enumerate() {
+                let term = Ratio::new(or_overflow(c.checked_mul(d))?, factorial.clone());
+                result[j] = or_overflow(result[j].checked_add(&term))?;
+            }
+        }
+        Ok(result)
+    }
+
+    // Term k, counting the first as 0; k may lie far before or after the known terms.
+    // Sums Δʲx0 · C(k, j) with the binomials built up one from the next.
+    pub fn at(&self, k: &T) -> Result<T, PolyError> {
+        let mut total = T::zero();
+        let mut binomial = T::one();
+        let mut j = T::zero();
+        for (i, d) in self.newton.iter().enumerate() {
+            if i > 0 {
+                // C(k, j) = C(k, j-1) · (k-j+1) / j, dividing out early to stay in range
+                let factor = or_overflow(k.checked_sub(&j))?;
+                j = j + T::one();
+                let g = binomial.gcd(&j);
+                let factor = factor / (j.clone() / g.clone());
+                binomial = or_overflow((binomial / g).checked_mul(&factor))?;
+            }
+            let term = or_overflow(d.checked_mul(&binomial))?;
+            total = or_overflow(total.checked_add(&term))?;
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    fn fit(xs: &[i128]) -> Result<Sequence<i128>, PolyError> {
+        Sequence::fit(xs.to_vec())
+    }
+
+    #[test]
+    fn squares() {
+        let p = fit(&[1, 4, 9, 16, 25]).unwrap();
+        assert_eq!(p.degree(), 2);
+        assert_eq!(p.newton(), [1, 3, 2]);
+        let one = Ratio::from_integer(1);
+        assert_eq!(p.coefficients(), Ok(vec![one, Ratio::from_integer(2), one]));
+        assert_eq!(p.at(&-4), Ok(9));
+        let x: i128 = 1_000_000_000_000;
+        assert_eq!(p.at(&x), Ok((x + 1) * (x + 1)));
+    }
+
+    #[test]
+    fn triangular() {
+        let triangular = Sequence::new(vec![1i128, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(triangular.degree(), 2);
+        assert_eq!(triangular.newton(), [1, 2, 1]);
+        assert_eq!(
+            triangular.coefficients(),
+            Ok(vec![
+                Ratio::from_integer(1),
+                Ratio::new(3, 2),
+                Ratio::new(1, 2)
+            ])
+        );
+        assert_eq!(triangular.at(&-1), Ok(0));
+        assert_eq!(triangular.at(&-3), Ok(1));
+        let k: i128 = 1_000_000_000_000;
+        assert_eq!(triangular.at(&k), Ok((k + 1) * (k + 2) / 2));
+        assert_eq!(triangular.at(&-k), Ok((1 - k) * (2 - k) / 2));
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(fit(&[7, 7]).unwrap().degree(), 0);
+        assert_eq!(fit(&[7, 7, 7, 7]).unwrap().at(&-50), Ok(7));
+        assert_eq!(fit(&[0, 0, 0]).unwrap().at(&5), Ok(0));
+        assert_eq!(fit(&[]), Err(PolyError::NotPolynomial));
+        let empty: Sequence<i128> = Sequence::new(Vec::new()).unwrap();
+        assert_eq!(empty.at(&3), Ok(0));
+    }
+
+    #[test]
+    fn not_polynomial() {
+        assert_eq!(fit(&[1, 2, 4, 8, 16, 32]), Err(PolyError::NotPolynomial));
+        assert_eq!(fit(&[1, 2]), Err(PolyError::NotPolynomial));
+        let line = Sequence::new(vec![1i128, 2]).unwrap();
+        assert_eq!(line.degree(), 1);
+        assert_eq!(line.at(&10), Ok(11));
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(fit(&[i128::MIN, i128::MAX]), Err(PolyError::Overflow));
+        let squares = fit(&[0, 1, 4, 9]).unwrap();
+        assert_eq!(squares.at(&(1 << 70)), Err(PolyError::Overflow));
+    }
+
+    #[test]
+    fn big_sequences() {
+        let tenth: Vec<BigInt> = (0..14u32).map(|k| BigInt::from(k).pow(10)).collect();
+        let seq = Sequence::fit(tenth).unwrap();
+        assert_eq!(seq.degree(), 10);
+        assert_eq!(seq.newton()[10], BigInt::from(3628800));
+        let k = BigInt::from(10).pow(12);
+        assert_eq!(seq.at(&k), Ok(BigInt::from(10).pow(120)));
+        assert_eq!(seq.at(&BigInt::from(-2)), Ok(BigInt::from(1024)));
+        // Far past any machine integer
+        let k = BigInt::from(10).pow(40);
+        assert_eq!(seq.at(&k), Ok(BigInt::from(10).pow(400)));
+        let mut tenth = seq.coefficients().unwrap();
+        assert_eq!(tenth.pop(), Some(Ratio::from_integer(BigInt::from(1))));
+        assert!(tenth
+            .iter()
+            .all(|c| *c == Ratio::from_integer(BigInt::from(0))));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::*;
+
+pub struct Entry {
+    pub day: u8,
+    pub part: u8,
+    pub solve: fn(&str, &[i64]) -> Option<String>,
+}
+
+macro_rules! entry {
+    ($day:literal, $part:literal, raw $module:ident :: $solve:ident) => {
+        Entry {
+            day: $day,
+            part: $part,
+            solve: |input, params| match params {
+                [] => Some($module::$solve(input).to_string()),
+                _ => None,
+            },
+        }
+    };
+    ($day:literal, $part:literal, $module:ident :: $solve:ident) => {
+        Entry {
+            day: $day,
+            part: $part,
+            solve: |input, params| match params {
+                [] => Some($module::$solve(&$module::input_generator(input)).to_string()),
+                _ => None,
+            },
+        }
+    };
+}
+
+pub static ENTRIES: &[Entry] = &[
+    entry!(1, 1, raw day1::solve_part1),
+    entry!(1, 2, raw day1::solve_part2),
+    entry!(2, 1, day2::solve_part1),
+    entry!(2, 2, day2::solve_part2),
+    entry!(3, 1, day3::solve_part1),
+    entry!(3, 2, day3::solve_part2),
+    entry!(4, 1, day4::solve_part1),
+    entry!(4, 2, day4::solve_part2),
+    entry!(5, 1, day5::solve_part1),
+    entry!(5, 2, day5::solve_part2),
+    entry!(6, 1, day6::solve_part1),
+    entry!(6, 2, day6::solve_part2),
+    entry!(7, 1, day7::solve_part1),
+    entry!(7, 2, day7::solve_part2),
+    entry!(8, 1, day8::solve_part1),
+    entry!(8, 2, day8::solve_part2),
+    entry!(9, 1, day9::solve_part1),
+    entry!(9, 2, day9::solve_part2),
+    entry!(10, 1, day10::solve_part1),
+    entry!(10, 2, day10::solve_part2),
+    Entry {
+        day: 11,
+        part: 1,
+        solve: |input, params| match params {
+            [] => Some(day11::solve_part1(&day11::input_generator(input)).to_string()),
+            &[scale] => Some(day11::solve(&day11::input_generator(input), scale).to_string()),
+            _ => None,
+        },
+    },
+    entry!(11, 2, day11::solve_part2),
+    entry!(12, 1, day12::solve_part1),
+    entry!(12, 2, day12::solve_part2),
+    entry!(13, 1, day13::solve_part1),
+    entry!(13, 2, day13::solve_part2),
+    entry!(14, 1, day14::solve_part1),
+    entry!(14, 2, day14::solve_part2),
+    entry!(15, 1, raw day15::solve_part1),
+    entry!(15, 2, raw day15::solve_part2),
+    entry!(16, 1, day16::solve_part1),
+    entry!(16, 2, day16::solve_part2),
+    entry!(17, 1, day17::solve_part1),
+    entry!(17, 2, day17::solve_part2),
+    entry!(18, 1, day18::solve_part1),
+    entry!(18, 2, day18::solve_part2),
+    entry!(19, 1, day19::solve_part1),
+    entry!(19, 2, day19::solve_part2),
+    entry!(20, 1, day20::solve_part1),
+    entry!(20, 2, day20::solve_part2),
+    Entry {
+        day: 21,
+        part: 1,
+        solve: |input, params| match params {
+            [] => Some(day21::solve_part1(&day21::input_generator(input)).to_string()),
+            &[n] => Some(day21::part1(&day21::input_generator(input), n as usize).to_string()),
+            _ => None,
+        },
+    },
+    Entry {
+        day: 21,
+        part: 2,
+        solve: |input, params| match params {
+            [] => Some(day21::solve_part2(&day21::input_generator(input)).to_string()),
+            &[n] => Some(day21::part2(&day21::input_generator(input), n as usize).to_string()),
+            _ => None,
+        },
+    },
+    entry!(22, 1, day22::solve_part1),
+    entry!(22, 2, day22::solve_part2),
+    entry!(23, 1, day23::solve_part1),
+    entry!(23, 2, day23::solve_part2),
+    Entry {
+        day: 24,
+        part: 1,
+        solve: |input, params| match params {
+            [] => Some(day24::solve_part1(&day24::input_generator(input)).to_string()),
+            &[from, to] => {
+                Some(day24::solve1(&day24::input_generator(input), from, to).to_string())
+            }
+            _ => None,
+        },
+    },
+    entry!(24, 2, day24::solve_part2),
+    entry!(25, 1, day25::solve_part1),
+];
+
+pub fn solver(day: u8, part: u8) -> Option<&'static Entry> {
+    ENTRIES.iter().find(|e| e.day == day && e.part == part)
+}
+
+// None when the day/part is unknown or does not take that many parameters
+pub fn solve(day: u8, part: u8, input: &str, params: &[i64]) -> Option<String> {
+    (solver(day, part)?.solve)(input, params)
+}
+
+// The input of fixtures/dayNN/<name>.txt, for tests that check more than the answers
+#[cfg(test)]
+pub(crate) fn fixture(day: u8, name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(format!("day{:02}", day))
+        .join(format!("{}.txt", name));
+    let input = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+    input.trim_end_matches('\n').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    fn check_answers(day: u8, input: &str, answers: &str, failures: &mut Vec<String>) -> usize {
+        let mut count = 0;
+        for line in answers.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (lhs, expected) = line
+                .split_once('=')
+                .expect("expected `partN [params] = answer`");
+            let mut words = lhs.split_whitespace();
+            let part: u8 = words
+                .next()
+                .and_then(|w| w.strip_prefix("part"))
+                .unwrap()
+                .parse()
+                .unwrap();
+            let params: Vec<i64> = words.map(|w| w.parse().unwrap()).collect();
+            let actual = solve(day, part, input, &params);
+            if actual.as_deref() != Some(expected.trim()) {
+                failures.push(format!("day {} `{}`: got {:?}", day, line, actual));
+            }
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn fixtures() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let mut failures = Vec::new();
+        let mut count = 0;
+        for dir in fs::read_dir(root).unwrap() {
+            let dir = dir.unwrap().path();
+            let name = dir.file_name().unwrap().to_str().unwrap();
+            let day: u8 = name.strip_prefix("day").unwrap().parse().unwrap();
+            for file in fs::read_dir(&dir).unwrap() {
+                let answers = file.unwrap().path();
+                if answers.extension().and_then(|ext| ext.to_str()) != Some("answers") {
+                    continue;
+                }
+                let input = fs::read_to_string(answers.with_extension("txt")).unwrap();
+                let expected = fs::read_to_string(&answers).unwrap();
+                count += check_answers(day, input.trim_end_matches('\n'), &expected, &mut failures);
+            }
+        }
+        assert!(count > 0, "no fixtures found");
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn every_fixture_has_answers() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        for dir in fs::read_dir(root).unwrap() {
+            for file in fs::read_dir(dir.unwrap().path()).unwrap() {
+                let input = file.unwrap().path();
+                if input.extension().and_then(|ext| ext.to_str()) == Some("txt") {
+                    assert!(input.with_extension("answers").exists(), "{:?}", input);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::{self, BufRead, ErrorKind};
+
+pub trait Accumulator {
+    type Record;
+    type Total;
+
+    fn feed(&mut self, record: &str) -> Self::Record;
+    fn total(&self) -> Self::Total;
+}
+
+pub struct Records<R, A> {
+    reader: R,
+    delimiter: u8,
+    buf: Vec<u8>,
+    acc: A,
+}
+
+impl<R, A> Records<R, A>
+where
+    R: BufRead,
+    A: Accumulator,
+{
+    pub fn total(&self) -> A::Total {
+        self.acc.total()
+    }
+
+    pub fn finish(mut self) -> io::Result<A::Total> {
+        for record in self.by_ref() {
+            record?;
+        }
+        Ok(self.total())
+    }
+}
+
+impl<R, A> Iterator for Records<R, A>
+where
+    R: BufRead,
+    A: Accumulator,
+{
+    type Item = io::Result<(A::Record, A::Total)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_until(self.delimiter, &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+            if self.buf.last() == Some(&self.delimiter) {
+                self.buf.pop();
+            }
+            let record = match std::str::from_utf8(&self.buf) {
+                Ok(text) => text.trim(),
+                Err(e) => return Some(Err(io::Error::new(ErrorKind::InvalidData, e))),
+            };
+            if record.is_empty() {
+                continue;
+            }
+            let result = self.acc.feed(record);
+            return Some(Ok((result, self.acc.total())));
+        }
+    }
+}
+
+pub fn lines<R: BufRead, A: Accumulator>(reader: R, acc: A) -> Records<R, A> {
+    split(reader, b'\n', acc)
+}
+
+pub fn split<R: BufRead, A: Accumulator>(reader: R, delimiter: u8, acc: A) -> Records<R, A> {
+    Records {
+        reader,
+        delimiter,
+        buf: Vec::new(),
+        acc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lengths(usize);
+
+    impl Accumulator for Lengths {
+        type Record = usize;
+        type Total = usize;
+
+        fn feed(&mut self, record: &str) -> usize {
+            self.0 += record.len();
+            record.len()
+        }
+
+        fn total(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn running_totals() {
+        let input = "ab\r\ncde\n\nf";
+        let records: Vec<(usize, usize)> = lines(input.as_bytes(), Lengths(0))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(records, vec![(2, 2), (3, 5), (1, 6)]);
+        assert_eq!(
+            split("x,yy,\n".as_bytes(), b',', Lengths(0))
+                .finish()
+                .unwrap(),
+            3
+        );
+    }
+
+    struct Synthetic {
+        line: &'static [u8],
+        lines: usize,
+        pos: usize,
+    }
+
+    impl io::Read for Synthetic {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.lines == 0 {
+                return Ok(0);
+            }
+            let n = buf.len().min(self.line.len() - self.pos);
+            buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+            self.pos += n;
+            if self.pos == self.line.len() {
+                self.pos = 0;
+                self.lines -= 1;
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn constant_memory() {
+        let source = Synthetic {
+            line: b"some fairly unremarkable record\n",
+            lines: 200_000,
+            pos: 0,
+        };
+        let mut records = lines(io::BufReader::new(source), Lengths(0));
+        assert_eq!(records.by_ref().count(), 200_000);
+        assert_eq!(records.total(), 200_000 * 31);
+        assert!(records.buf.capacity() < 64);
+    }
+}